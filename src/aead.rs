@@ -1,88 +1,71 @@
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
 use alloc::boxed::Box;
 
-use chacha20poly1305::aead::Buffer;
-use chacha20poly1305::{AeadInPlace, KeyInit, KeySizeUser};
-use rustls::crypto::cipher::{
-    self, AeadKey, BorrowedPayload, Iv, PrefixedPayload, UnsupportedOperationError, NONCE_LEN,
-};
-use rustls::{ConnectionTrafficSecrets, ContentType, ProtocolVersion};
-
-pub struct Chacha20Poly1305;
-
-impl cipher::Tls13AeadAlgorithm for Chacha20Poly1305 {
-    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
-        Box::new(Tls13Cipher(
-            chacha20poly1305::ChaCha20Poly1305::new_from_slice(key.as_ref()).unwrap(),
-            iv,
-        ))
-    }
-
-    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
-        Box::new(Tls13Cipher(
-            chacha20poly1305::ChaCha20Poly1305::new_from_slice(key.as_ref()).unwrap(),
-            iv,
-        ))
-    }
-
-    fn key_len(&self) -> usize {
-        chacha20poly1305::ChaCha20Poly1305::key_size()
-    }
-
-    fn extract_keys(
-        &self,
-        key: AeadKey,
-        iv: Iv,
-    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
-        Ok(ConnectionTrafficSecrets::Chacha20Poly1305 { key, iv })
-    }
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use aead::consts::{U0, U12, U16};
+use aead::generic_array::typenum::Unsigned;
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use aead::AeadCore;
+use aead::{AeadInPlace, Buffer};
+use rustls::crypto::cipher::{self, BorrowedPayload, PrefixedPayload};
+use rustls::{ContentType, ProtocolVersion};
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use crate::backend;
+
+#[cfg(any(feature = "ccm", feature = "ccm-8"))]
+pub mod ccm;
+#[cfg(feature = "chacha")]
+pub mod chacha;
+#[cfg(all(
+    feature = "tls12",
+    any(feature = "aes-gcm", feature = "ccm", feature = "ccm-8")
+))]
+mod explicit_nonce;
+#[cfg(feature = "aes-gcm")]
+pub mod gcm;
+#[cfg(feature = "chacha")]
+mod quic;
+
+/// Runs the AEADs that a [`backend::Backend`] can replace through that backend.
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+struct BackendAead(Box<dyn backend::Aead>);
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+impl AeadCore for BackendAead {
+    type NonceSize = U12;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
 }
 
-impl cipher::Tls12AeadAlgorithm for Chacha20Poly1305 {
-    fn encrypter(
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+impl AeadInPlace for BackendAead {
+    fn encrypt_in_place_detached(
         &self,
-        key: cipher::AeadKey,
-        iv: &[u8],
-        _: &[u8],
-    ) -> Box<dyn cipher::MessageEncrypter> {
-        Box::new(Tls12Cipher(
-            chacha20poly1305::ChaCha20Poly1305::new_from_slice(key.as_ref()).unwrap(),
-            cipher::Iv::copy(iv),
-        ))
-    }
-
-    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
-        Box::new(Tls12Cipher(
-            chacha20poly1305::ChaCha20Poly1305::new_from_slice(key.as_ref()).unwrap(),
-            cipher::Iv::copy(iv),
-        ))
-    }
-
-    fn key_block_shape(&self) -> cipher::KeyBlockShape {
-        cipher::KeyBlockShape {
-            enc_key_len: 32,
-            fixed_iv_len: 12,
-            explicit_nonce_len: 0,
-        }
+        nonce: &aead::Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<aead::Tag<Self>> {
+        self.0
+            .seal(nonce.as_ref(), associated_data, buffer)
+            .map(Into::into)
     }
 
-    fn extract_keys(
+    fn decrypt_in_place_detached(
         &self,
-        key: AeadKey,
-        iv: &[u8],
-        _explicit: &[u8],
-    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
-        // This should always be true because KeyBlockShape and the Iv nonce len are in agreement.
-        debug_assert_eq!(NONCE_LEN, iv.len());
-        Ok(ConnectionTrafficSecrets::Chacha20Poly1305 {
-            key,
-            iv: Iv::new(iv[..].try_into().unwrap()),
-        })
+        nonce: &aead::Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &aead::Tag<Self>,
+    ) -> aead::Result<()> {
+        self.0
+            .open(nonce.as_ref(), associated_data, buffer, tag.as_ref())
     }
 }
 
-struct Tls13Cipher(chacha20poly1305::ChaCha20Poly1305, cipher::Iv);
+struct Tls13Cipher<C>(C, cipher::Iv);
 
-impl cipher::MessageEncrypter for Tls13Cipher {
+impl<C: AeadInPlace + Send + Sync> cipher::MessageEncrypter for Tls13Cipher<C> {
     fn encrypt(
         &mut self,
         m: cipher::OutboundPlainMessage<'_>,
@@ -95,11 +78,15 @@ impl cipher::MessageEncrypter for Tls13Cipher {
         payload.extend_from_chunks(&m.payload);
         payload.extend_from_slice(&m.typ.to_array());
 
-        let nonce = chacha20poly1305::Nonce::from(cipher::Nonce::new(&self.1, seq).0);
+        let nonce = cipher::Nonce::new(&self.1, seq).0;
         let aad = cipher::make_tls13_aad(total_len);
 
         self.0
-            .encrypt_in_place(&nonce, &aad, &mut EncryptBufferAdapter(&mut payload))
+            .encrypt_in_place(
+                aead::Nonce::<C>::from_slice(&nonce),
+                &aad,
+                &mut EncryptBufferAdapter(&mut payload),
+            )
             .map_err(|_| rustls::Error::EncryptError)
             .map(|_| {
                 cipher::OutboundOpaqueMessage::new(
@@ -111,82 +98,32 @@ impl cipher::MessageEncrypter for Tls13Cipher {
     }
 
     fn encrypted_payload_len(&self, payload_len: usize) -> usize {
-        payload_len + 1 + CHACHAPOLY1305_OVERHEAD
+        payload_len + 1 + C::TagSize::USIZE
     }
 }
 
-impl cipher::MessageDecrypter for Tls13Cipher {
+impl<C: AeadInPlace + Send + Sync> cipher::MessageDecrypter for Tls13Cipher<C> {
     fn decrypt<'a>(
         &mut self,
         mut m: cipher::InboundOpaqueMessage<'a>,
         seq: u64,
     ) -> Result<cipher::InboundPlainMessage<'a>, rustls::Error> {
         let payload = &mut m.payload;
-        let nonce = chacha20poly1305::Nonce::from(cipher::Nonce::new(&self.1, seq).0);
+        let nonce = cipher::Nonce::new(&self.1, seq).0;
         let aad = cipher::make_tls13_aad(payload.len());
 
         self.0
-            .decrypt_in_place(&nonce, &aad, &mut DecryptBufferAdapter(payload))
+            .decrypt_in_place(
+                aead::Nonce::<C>::from_slice(&nonce),
+                &aad,
+                &mut DecryptBufferAdapter(payload),
+            )
             .map_err(|_| rustls::Error::DecryptError)?;
 
         m.into_tls13_unpadded_message()
     }
 }
 
-struct Tls12Cipher(chacha20poly1305::ChaCha20Poly1305, cipher::Iv);
-
-impl cipher::MessageEncrypter for Tls12Cipher {
-    fn encrypt(
-        &mut self,
-        m: cipher::OutboundPlainMessage,
-        seq: u64,
-    ) -> Result<cipher::OutboundOpaqueMessage, rustls::Error> {
-        let total_len = self.encrypted_payload_len(m.payload.len());
-
-        let mut payload = PrefixedPayload::with_capacity(total_len);
-        payload.extend_from_chunks(&m.payload);
-        payload.extend_from_slice(&m.typ.to_array());
-
-        let nonce = chacha20poly1305::Nonce::from(cipher::Nonce::new(&self.1, seq).0);
-        let aad = cipher::make_tls12_aad(seq, m.typ, m.version, m.payload.len());
-
-        self.0
-            .encrypt_in_place(&nonce, &aad, &mut EncryptBufferAdapter(&mut payload))
-            .map_err(|_| rustls::Error::EncryptError)
-            .map(|_| cipher::OutboundOpaqueMessage::new(m.typ, m.version, payload))
-    }
-
-    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
-        payload_len + CHACHAPOLY1305_OVERHEAD
-    }
-}
-
-impl cipher::MessageDecrypter for Tls12Cipher {
-    fn decrypt<'a>(
-        &mut self,
-        mut m: cipher::InboundOpaqueMessage<'a>,
-        seq: u64,
-    ) -> Result<cipher::InboundPlainMessage<'a>, rustls::Error> {
-        let payload = &m.payload;
-        let nonce = chacha20poly1305::Nonce::from(cipher::Nonce::new(&self.1, seq).0);
-        let aad = cipher::make_tls12_aad(
-            seq,
-            m.typ,
-            m.version,
-            payload.len() - CHACHAPOLY1305_OVERHEAD,
-        );
-
-        let payload = &mut m.payload;
-        self.0
-            .decrypt_in_place(&nonce, &aad, &mut DecryptBufferAdapter(payload))
-            .map_err(|_| rustls::Error::DecryptError)?;
-
-        Ok(m.into_plain_message())
-    }
-}
-
-const CHACHAPOLY1305_OVERHEAD: usize = 16;
-
 struct EncryptBufferAdapter<'a>(&'a mut PrefixedPayload);
 
 impl AsRef<[u8]> for EncryptBufferAdapter<'_> {
@@ -202,7 +139,7 @@ impl AsMut<[u8]> for EncryptBufferAdapter<'_> {
 }
 
 impl Buffer for EncryptBufferAdapter<'_> {
-    fn extend_from_slice(&mut self, other: &[u8]) -> chacha20poly1305::aead::Result<()> {
+    fn extend_from_slice(&mut self, other: &[u8]) -> aead::Result<()> {
         self.0.extend_from_slice(other);
         Ok(())
     }
@@ -226,7 +163,7 @@ impl AsMut<[u8]> for DecryptBufferAdapter<'_, '_> {
 }
 
 impl Buffer for DecryptBufferAdapter<'_, '_> {
-    fn extend_from_slice(&mut self, _: &[u8]) -> chacha20poly1305::aead::Result<()> {
+    fn extend_from_slice(&mut self, _: &[u8]) -> aead::Result<()> {
         unreachable!("not used by `AeadInPlace::decrypt_in_place`")
     }
 
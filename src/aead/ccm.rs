@@ -0,0 +1,147 @@
+use alloc::boxed::Box;
+
+use ccm::{KeyInit, KeySizeUser};
+use rustls::crypto::cipher::{self, AeadKey, Iv, UnsupportedOperationError};
+use rustls::ConnectionTrafficSecrets;
+
+#[cfg(feature = "tls12")]
+use super::explicit_nonce::{self, Tls12Decrypter, Tls12Encrypter};
+use super::Tls13Cipher;
+
+// TLS uses a 12-byte nonce, i.e. CCM with a 3-byte length field (RFC6655)
+#[cfg(feature = "ccm")]
+type Ccm = ccm::Ccm<aes::Aes128, ccm::consts::U16, ccm::consts::U12>;
+#[cfg(feature = "ccm-8")]
+type Ccm8 = ccm::Ccm<aes::Aes128, ccm::consts::U8, ccm::consts::U12>;
+
+#[cfg(feature = "ccm")]
+pub struct Aes128Ccm;
+
+#[cfg(feature = "ccm")]
+impl cipher::Tls13AeadAlgorithm for Aes128Ccm {
+    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls13Cipher(Ccm::new_from_slice(key.as_ref()).unwrap(), iv))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls13Cipher(Ccm::new_from_slice(key.as_ref()).unwrap(), iv))
+    }
+
+    fn key_len(&self) -> usize {
+        Ccm::key_size()
+    }
+
+    // `ConnectionTrafficSecrets` has no CCM variants
+    fn extract_keys(
+        &self,
+        _key: AeadKey,
+        _iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Err(UnsupportedOperationError)
+    }
+}
+
+#[cfg(all(feature = "ccm", feature = "tls12"))]
+impl cipher::Tls12AeadAlgorithm for Aes128Ccm {
+    fn encrypter(
+        &self,
+        key: cipher::AeadKey,
+        iv: &[u8],
+        extra: &[u8],
+    ) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls12Encrypter(
+            Ccm::new_from_slice(key.as_ref()).unwrap(),
+            explicit_nonce::iv(iv, extra),
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls12Decrypter(
+            Ccm::new_from_slice(key.as_ref()).unwrap(),
+            iv.try_into().unwrap(),
+        ))
+    }
+
+    fn key_block_shape(&self) -> cipher::KeyBlockShape {
+        cipher::KeyBlockShape {
+            enc_key_len: 16,
+            fixed_iv_len: explicit_nonce::FIXED_IV_LEN,
+            explicit_nonce_len: explicit_nonce::EXPLICIT_NONCE_LEN,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        _key: AeadKey,
+        _iv: &[u8],
+        _explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Err(UnsupportedOperationError)
+    }
+}
+
+/// AES-128-CCM with a truncated 8-byte tag.
+#[cfg(feature = "ccm-8")]
+pub struct Aes128Ccm8;
+
+#[cfg(feature = "ccm-8")]
+impl cipher::Tls13AeadAlgorithm for Aes128Ccm8 {
+    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls13Cipher(Ccm8::new_from_slice(key.as_ref()).unwrap(), iv))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls13Cipher(Ccm8::new_from_slice(key.as_ref()).unwrap(), iv))
+    }
+
+    fn key_len(&self) -> usize {
+        Ccm8::key_size()
+    }
+
+    fn extract_keys(
+        &self,
+        _key: AeadKey,
+        _iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Err(UnsupportedOperationError)
+    }
+}
+
+#[cfg(all(feature = "ccm-8", feature = "tls12"))]
+impl cipher::Tls12AeadAlgorithm for Aes128Ccm8 {
+    fn encrypter(
+        &self,
+        key: cipher::AeadKey,
+        iv: &[u8],
+        extra: &[u8],
+    ) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls12Encrypter(
+            Ccm8::new_from_slice(key.as_ref()).unwrap(),
+            explicit_nonce::iv(iv, extra),
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls12Decrypter(
+            Ccm8::new_from_slice(key.as_ref()).unwrap(),
+            iv.try_into().unwrap(),
+        ))
+    }
+
+    fn key_block_shape(&self) -> cipher::KeyBlockShape {
+        cipher::KeyBlockShape {
+            enc_key_len: 16,
+            fixed_iv_len: explicit_nonce::FIXED_IV_LEN,
+            explicit_nonce_len: explicit_nonce::EXPLICIT_NONCE_LEN,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        _key: AeadKey,
+        _iv: &[u8],
+        _explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Err(UnsupportedOperationError)
+    }
+}
@@ -0,0 +1,149 @@
+use alloc::boxed::Box;
+
+#[cfg(feature = "tls12")]
+use aead::AeadInPlace;
+use rustls::crypto::cipher::{self, AeadKey, Iv, UnsupportedOperationError};
+#[cfg(feature = "tls12")]
+use rustls::crypto::cipher::{PrefixedPayload, NONCE_LEN};
+use rustls::ConnectionTrafficSecrets;
+
+use super::{BackendAead, Tls13Cipher};
+#[cfg(feature = "tls12")]
+use super::{DecryptBufferAdapter, EncryptBufferAdapter};
+use crate::backend::backend;
+
+pub struct Chacha20Poly1305;
+
+impl cipher::Tls13AeadAlgorithm for Chacha20Poly1305 {
+    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().chacha20_poly1305(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().chacha20_poly1305(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn key_len(&self) -> usize {
+        32
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Chacha20Poly1305 { key, iv })
+    }
+}
+
+#[cfg(feature = "tls12")]
+impl cipher::Tls12AeadAlgorithm for Chacha20Poly1305 {
+    fn encrypter(
+        &self,
+        key: cipher::AeadKey,
+        iv: &[u8],
+        _: &[u8],
+    ) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls12Cipher(
+            BackendAead(backend().chacha20_poly1305(key.as_ref())),
+            cipher::Iv::copy(iv),
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls12Cipher(
+            BackendAead(backend().chacha20_poly1305(key.as_ref())),
+            cipher::Iv::copy(iv),
+        ))
+    }
+
+    fn key_block_shape(&self) -> cipher::KeyBlockShape {
+        cipher::KeyBlockShape {
+            enc_key_len: 32,
+            fixed_iv_len: 12,
+            explicit_nonce_len: 0,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        _explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        // This should always be true because KeyBlockShape and the Iv nonce len are in agreement.
+        debug_assert_eq!(NONCE_LEN, iv.len());
+        Ok(ConnectionTrafficSecrets::Chacha20Poly1305 {
+            key,
+            iv: Iv::new(iv[..].try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(feature = "tls12")]
+struct Tls12Cipher(BackendAead, cipher::Iv);
+
+#[cfg(feature = "tls12")]
+impl cipher::MessageEncrypter for Tls12Cipher {
+    fn encrypt(
+        &mut self,
+        m: cipher::OutboundPlainMessage,
+        seq: u64,
+    ) -> Result<cipher::OutboundOpaqueMessage, rustls::Error> {
+        let total_len = self.encrypted_payload_len(m.payload.len());
+
+        // unlike TLS1.3, the content type stays in the record header (RFC7905)
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        payload.extend_from_chunks(&m.payload);
+
+        let nonce = aead::Nonce::<BackendAead>::from(cipher::Nonce::new(&self.1, seq).0);
+        let aad = cipher::make_tls12_aad(seq, m.typ, m.version, m.payload.len());
+
+        self.0
+            .encrypt_in_place(&nonce, &aad, &mut EncryptBufferAdapter(&mut payload))
+            .map_err(|_| rustls::Error::EncryptError)
+            .map(|_| cipher::OutboundOpaqueMessage::new(m.typ, m.version, payload))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        payload_len + CHACHAPOLY1305_OVERHEAD
+    }
+}
+
+#[cfg(feature = "tls12")]
+impl cipher::MessageDecrypter for Tls12Cipher {
+    fn decrypt<'a>(
+        &mut self,
+        mut m: cipher::InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<cipher::InboundPlainMessage<'a>, rustls::Error> {
+        let payload = &m.payload;
+        if payload.len() < CHACHAPOLY1305_OVERHEAD {
+            return Err(rustls::Error::DecryptError);
+        }
+
+        let nonce = aead::Nonce::<BackendAead>::from(cipher::Nonce::new(&self.1, seq).0);
+        let aad = cipher::make_tls12_aad(
+            seq,
+            m.typ,
+            m.version,
+            payload.len() - CHACHAPOLY1305_OVERHEAD,
+        );
+
+        let payload = &mut m.payload;
+        self.0
+            .decrypt_in_place(&nonce, &aad, &mut DecryptBufferAdapter(payload))
+            .map_err(|_| rustls::Error::DecryptError)?;
+
+        Ok(m.into_plain_message())
+    }
+}
+
+#[cfg(feature = "tls12")]
+const CHACHAPOLY1305_OVERHEAD: usize = 16;
@@ -0,0 +1,97 @@
+use aead::generic_array::typenum::Unsigned;
+use aead::generic_array::GenericArray;
+use aead::AeadInPlace;
+use rustls::crypto::cipher::{self, Iv, PrefixedPayload, NONCE_LEN};
+
+/// The TLS1.2 GCM nonce is the 4-byte implicit salt from the key block,
+/// followed by an 8-byte explicit part that is sent with each record (RFC5288).
+/// CCM records use the same layout (RFC6655).
+pub(super) fn iv(write_iv: &[u8], explicit: &[u8]) -> Iv {
+    debug_assert_eq!(write_iv.len(), FIXED_IV_LEN);
+    debug_assert_eq!(explicit.len(), EXPLICIT_NONCE_LEN);
+
+    let mut iv = [0u8; NONCE_LEN];
+    iv[..FIXED_IV_LEN].copy_from_slice(write_iv);
+    iv[FIXED_IV_LEN..].copy_from_slice(explicit);
+    Iv::new(iv)
+}
+
+pub(super) struct Tls12Encrypter<C>(pub(super) C, pub(super) cipher::Iv);
+
+impl<C: AeadInPlace + Send + Sync> cipher::MessageEncrypter for Tls12Encrypter<C> {
+    fn encrypt(
+        &mut self,
+        m: cipher::OutboundPlainMessage,
+        seq: u64,
+    ) -> Result<cipher::OutboundOpaqueMessage, rustls::Error> {
+        let total_len = self.encrypted_payload_len(m.payload.len());
+
+        // We use the sequence number as the explicit part of the nonce,
+        // XORed into the random value from the key block.
+        let nonce = cipher::Nonce::new(&self.1, seq).0;
+
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        payload.extend_from_slice(&nonce[FIXED_IV_LEN..]);
+        payload.extend_from_chunks(&m.payload);
+
+        let aad = cipher::make_tls12_aad(seq, m.typ, m.version, m.payload.len());
+
+        let tag = self
+            .0
+            .encrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                &mut payload.as_mut()[EXPLICIT_NONCE_LEN..],
+            )
+            .map_err(|_| rustls::Error::EncryptError)?;
+        payload.extend_from_slice(&tag);
+
+        Ok(cipher::OutboundOpaqueMessage::new(
+            m.typ, m.version, payload,
+        ))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        payload_len + EXPLICIT_NONCE_LEN + C::TagSize::USIZE
+    }
+}
+
+pub(super) struct Tls12Decrypter<C>(pub(super) C, pub(super) [u8; FIXED_IV_LEN]);
+
+impl<C: AeadInPlace + Send + Sync> cipher::MessageDecrypter for Tls12Decrypter<C> {
+    fn decrypt<'a>(
+        &mut self,
+        mut m: cipher::InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<cipher::InboundPlainMessage<'a>, rustls::Error> {
+        let payload = &mut m.payload;
+        if payload.len() < EXPLICIT_NONCE_LEN + C::TagSize::USIZE {
+            return Err(rustls::Error::DecryptError);
+        }
+        let plain_len = payload.len() - EXPLICIT_NONCE_LEN - C::TagSize::USIZE;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..FIXED_IV_LEN].copy_from_slice(&self.1);
+        nonce[FIXED_IV_LEN..].copy_from_slice(&payload[..EXPLICIT_NONCE_LEN]);
+
+        let aad = cipher::make_tls12_aad(seq, m.typ, m.version, plain_len);
+
+        let (ciphertext, tag) = payload[EXPLICIT_NONCE_LEN..].split_at_mut(plain_len);
+        self.0
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                ciphertext,
+                GenericArray::from_slice(tag),
+            )
+            .map_err(|_| rustls::Error::DecryptError)?;
+
+        payload.copy_within(EXPLICIT_NONCE_LEN..EXPLICIT_NONCE_LEN + plain_len, 0);
+        payload.truncate(plain_len);
+
+        Ok(m.into_plain_message())
+    }
+}
+
+pub(super) const FIXED_IV_LEN: usize = 4;
+pub(super) const EXPLICIT_NONCE_LEN: usize = 8;
@@ -0,0 +1,153 @@
+use alloc::boxed::Box;
+
+use rustls::crypto::cipher::{self, AeadKey, Iv, UnsupportedOperationError};
+use rustls::ConnectionTrafficSecrets;
+
+#[cfg(feature = "tls12")]
+use super::explicit_nonce::{self, Tls12Decrypter, Tls12Encrypter};
+use super::{BackendAead, Tls13Cipher};
+use crate::backend::backend;
+
+pub struct Aes128Gcm;
+
+impl cipher::Tls13AeadAlgorithm for Aes128Gcm {
+    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn key_len(&self) -> usize {
+        16
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Aes128Gcm { key, iv })
+    }
+}
+
+#[cfg(feature = "tls12")]
+impl cipher::Tls12AeadAlgorithm for Aes128Gcm {
+    fn encrypter(
+        &self,
+        key: cipher::AeadKey,
+        iv: &[u8],
+        extra: &[u8],
+    ) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls12Encrypter(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            explicit_nonce::iv(iv, extra),
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls12Decrypter(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv.try_into().unwrap(),
+        ))
+    }
+
+    fn key_block_shape(&self) -> cipher::KeyBlockShape {
+        cipher::KeyBlockShape {
+            enc_key_len: 16,
+            fixed_iv_len: explicit_nonce::FIXED_IV_LEN,
+            explicit_nonce_len: explicit_nonce::EXPLICIT_NONCE_LEN,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Aes128Gcm {
+            key,
+            iv: explicit_nonce::iv(iv, explicit),
+        })
+    }
+}
+
+pub struct Aes256Gcm;
+
+impl cipher::Tls13AeadAlgorithm for Aes256Gcm {
+    fn encrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: cipher::Iv) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls13Cipher(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn key_len(&self) -> usize {
+        32
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Aes256Gcm { key, iv })
+    }
+}
+
+#[cfg(feature = "tls12")]
+impl cipher::Tls12AeadAlgorithm for Aes256Gcm {
+    fn encrypter(
+        &self,
+        key: cipher::AeadKey,
+        iv: &[u8],
+        extra: &[u8],
+    ) -> Box<dyn cipher::MessageEncrypter> {
+        Box::new(Tls12Encrypter(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            explicit_nonce::iv(iv, extra),
+        ))
+    }
+
+    fn decrypter(&self, key: cipher::AeadKey, iv: &[u8]) -> Box<dyn cipher::MessageDecrypter> {
+        Box::new(Tls12Decrypter(
+            BackendAead(backend().aes_gcm(key.as_ref())),
+            iv.try_into().unwrap(),
+        ))
+    }
+
+    fn key_block_shape(&self) -> cipher::KeyBlockShape {
+        cipher::KeyBlockShape {
+            enc_key_len: 32,
+            fixed_iv_len: explicit_nonce::FIXED_IV_LEN,
+            explicit_nonce_len: explicit_nonce::EXPLICIT_NONCE_LEN,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Aes256Gcm {
+            key,
+            iv: explicit_nonce::iv(iv, explicit),
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::boxed::Box;
+
+use aead::generic_array::typenum::Unsigned;
+use aead::{AeadCore, AeadInPlace};
+use chacha20::cipher::consts::U10;
+use chacha20::cipher::{KeyIvInit, StreamCipherCore, StreamCipherSeekCore};
+use rustls::crypto::cipher::{self, AeadKey, Iv};
+use rustls::quic;
+
+use super::BackendAead;
+use crate::backend::backend;
+
+impl quic::Algorithm for super::chacha::Chacha20Poly1305 {
+    fn packet_key(&self, key: AeadKey, iv: Iv) -> Box<dyn quic::PacketKey> {
+        Box::new(PacketKey(
+            BackendAead(backend().chacha20_poly1305(key.as_ref())),
+            iv,
+        ))
+    }
+
+    fn header_protection_key(&self, key: AeadKey) -> Box<dyn quic::HeaderProtectionKey> {
+        Box::new(HeaderProtectionKey(key.as_ref().try_into().unwrap()))
+    }
+
+    fn aead_key_len(&self) -> usize {
+        32
+    }
+}
+
+struct PacketKey(BackendAead, Iv);
+
+impl quic::PacketKey for PacketKey {
+    fn encrypt_in_place(
+        &self,
+        packet_number: u64,
+        header: &[u8],
+        payload: &mut [u8],
+    ) -> Result<quic::Tag, rustls::Error> {
+        let nonce = cipher::Nonce::new(&self.1, packet_number).0;
+        self.0
+            .encrypt_in_place_detached(&nonce.into(), header, payload)
+            .map(|tag| quic::Tag::from(tag.as_slice()))
+            .map_err(|_| rustls::Error::EncryptError)
+    }
+
+    fn decrypt_in_place<'a>(
+        &self,
+        packet_number: u64,
+        header: &[u8],
+        payload: &'a mut [u8],
+    ) -> Result<&'a [u8], rustls::Error> {
+        let plain_len = payload
+            .len()
+            .checked_sub(self.tag_len())
+            .ok_or(rustls::Error::DecryptError)?;
+        let (plain, tag) = payload.split_at_mut(plain_len);
+
+        let nonce = cipher::Nonce::new(&self.1, packet_number).0;
+        self.0
+            .decrypt_in_place_detached(&nonce.into(), header, plain, (&*tag).into())
+            .map_err(|_| rustls::Error::DecryptError)?;
+        Ok(plain)
+    }
+
+    fn tag_len(&self) -> usize {
+        <BackendAead as AeadCore>::TagSize::USIZE
+    }
+
+    fn confidentiality_limit(&self) -> u64 {
+        u64::MAX
+    }
+
+    // RFC9001 section 6.6
+    fn integrity_limit(&self) -> u64 {
+        1 << 36
+    }
+}
+
+/// ChaCha20 header protection (RFC9001 section 5.4.4)
+struct HeaderProtectionKey([u8; 32]);
+
+impl HeaderProtectionKey {
+    fn xor_in_place(
+        &self,
+        sample: &[u8],
+        first: &mut u8,
+        packet_number: &mut [u8],
+        masked: bool,
+    ) -> Result<(), rustls::Error> {
+        if sample.len() != SAMPLE_LEN {
+            return Err(rustls::Error::General("sample of invalid length".into()));
+        }
+        if packet_number.len() > MAX_PN_LEN {
+            return Err(rustls::Error::General("packet number too long".into()));
+        }
+
+        // the first 4 bytes of the sample are the block counter, the rest is the nonce; the
+        // peer picks both, so the mask is one raw block, which has no keystream limit to hit
+        let (counter, nonce) = sample.split_at(4);
+        let mut chacha = chacha20::ChaChaCore::<U10>::new(&self.0.into(), nonce.into());
+        chacha.set_block_pos(u32::from_le_bytes(counter.try_into().unwrap()));
+        let mut mask = Default::default();
+        chacha.write_keystream_block(&mut mask);
+
+        let bits = if *first & LONG_HEADER_FORM == LONG_HEADER_FORM {
+            0x0f
+        } else {
+            0x1f
+        };
+        // the packet number length is only readable once `first` is unmasked
+        let first_plain = if masked {
+            *first ^ (mask[0] & bits)
+        } else {
+            *first
+        };
+        let pn_len = (first_plain & 0x03) as usize + 1;
+
+        *first ^= mask[0] & bits;
+        for (pn, mask) in packet_number.iter_mut().zip(&mask[1..]).take(pn_len) {
+            *pn ^= mask;
+        }
+
+        Ok(())
+    }
+}
+
+impl quic::HeaderProtectionKey for HeaderProtectionKey {
+    fn encrypt_in_place(
+        &self,
+        sample: &[u8],
+        first: &mut u8,
+        packet_number: &mut [u8],
+    ) -> Result<(), rustls::Error> {
+        self.xor_in_place(sample, first, packet_number, false)
+    }
+
+    fn decrypt_in_place(
+        &self,
+        sample: &[u8],
+        first: &mut u8,
+        packet_number: &mut [u8],
+    ) -> Result<(), rustls::Error> {
+        self.xor_in_place(sample, first, packet_number, true)
+    }
+
+    fn sample_len(&self) -> usize {
+        SAMPLE_LEN
+    }
+}
+
+const SAMPLE_LEN: usize = 16;
+const MAX_PN_LEN: usize = 4;
+const LONG_HEADER_FORM: u8 = 0x80;
@@ -0,0 +1,104 @@
+use alloc::boxed::Box;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use rustls::crypto::{hash, hmac};
+
+#[cfg(feature = "std")]
+mod recording;
+mod software;
+
+#[cfg(feature = "std")]
+pub use recording::{Call, Recording};
+
+/// The primitives of the provider that a hardware accelerator can take over, e.g. the
+/// HASH and CRYP peripherals of the STM32F415/417/437/439 (the F429ZI of the demo has neither).
+///
+/// Every method defaults to [`Software`], so an implementation only overrides what its
+/// peripheral supports: the STM32F4 HASH has no SHA-384 and CRYP has no ChaCha20.
+/// SHA-384, HMAC-SHA384 and AES-CCM always run in software.
+pub trait Backend: Send + Sync {
+    fn sha256(&self) -> Box<dyn hash::Context> {
+        software::sha256()
+    }
+
+    fn hmac_sha256(&self, key: &[u8]) -> Box<dyn hmac::Key> {
+        software::hmac_sha256(key)
+    }
+
+    /// `key` is 16 bytes for AES-128-GCM and 32 bytes for AES-256-GCM.
+    #[cfg(feature = "aes-gcm")]
+    fn aes_gcm(&self, key: &[u8]) -> Box<dyn Aead> {
+        software::aes_gcm(key)
+    }
+
+    #[cfg(feature = "chacha")]
+    fn chacha20_poly1305(&self, key: &[u8]) -> Box<dyn Aead> {
+        software::chacha20_poly1305(key)
+    }
+}
+
+/// An AEAD with a 12-byte nonce and a detached 16-byte tag, working in place
+/// (the interface of the CRYP GCM mode).
+pub trait Aead: Send + Sync {
+    /// Encrypts `buffer` and returns the tag.
+    fn seal(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; 16], aead::Error>;
+
+    /// Decrypts `buffer` if `tag` is valid; `buffer` is unspecified otherwise.
+    fn open(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8; 16],
+    ) -> Result<(), aead::Error>;
+}
+
+/// The pure Rust crates (`sha2`, `hmac`, `aes-gcm` and `chacha20poly1305`), used until
+/// [`set_backend`] is called.
+#[derive(Debug)]
+pub struct Software;
+
+impl Backend for Software {}
+
+/// Returned by [`set_backend`] when a backend was already set.
+#[derive(Debug)]
+pub struct SetBackendError;
+
+impl core::fmt::Display for SetBackendError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the crypto backend was already set")
+    }
+}
+
+/// Null until [`set_backend`] leaks a thin pointer to the backend's (fat) reference.
+static BACKEND: AtomicPtr<&'static dyn Backend> = AtomicPtr::new(ptr::null_mut());
+
+/// Replaces [`Software`] with `backend`, once, typically before the first handshake.
+/// Hashes, keys and record encrypters created before keep using the previous backend.
+pub fn set_backend(backend: &'static dyn Backend) -> Result<(), SetBackendError> {
+    let new = Box::into_raw(Box::new(backend));
+    BACKEND
+        .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        .map(|_| ())
+        .map_err(|_| {
+            // SAFETY: `new` came from `Box::into_raw` above and was never published.
+            drop(unsafe { Box::from_raw(new) });
+            SetBackendError
+        })
+}
+
+pub(crate) fn backend() -> &'static dyn Backend {
+    let backend = BACKEND.load(Ordering::Acquire);
+    if backend.is_null() {
+        &Software
+    } else {
+        // SAFETY: a non-null pointer was leaked by `set_backend` and is never freed or changed.
+        unsafe { *backend }
+    }
+}
@@ -0,0 +1,105 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use std::sync::Mutex;
+
+use rustls::crypto::{hash, hmac};
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use super::Aead;
+use super::{Backend, Software};
+
+/// A call that reached a [`Recording`] backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Call {
+    Sha256,
+    HmacSha256,
+    AesGcm {
+        key_len: usize,
+    },
+    ChaCha20Poly1305,
+    /// An AEAD from the backend encrypted `len` bytes.
+    Seal {
+        len: usize,
+    },
+    /// An AEAD from the backend decrypted `len` bytes, whether the tag was valid or not.
+    Open {
+        len: usize,
+    },
+}
+
+/// A mock [`Backend`] that records the calls it gets and forwards them to [`Software`],
+/// to check on the host which operations a hardware backend would see:
+///
+/// ```ignore
+/// let recording: &'static Recording = Box::leak(Box::default());
+/// backend::set_backend(recording).unwrap();
+/// // ... complete a handshake with `provider()`
+/// assert!(recording.take().contains(&Call::HmacSha256));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+impl Recording {
+    /// Returns the calls recorded so far and forgets them.
+    pub fn take(&self) -> Vec<Call> {
+        core::mem::take(&mut *self.calls.lock().unwrap())
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl Backend for Recording {
+    fn sha256(&self) -> Box<dyn hash::Context> {
+        self.record(Call::Sha256);
+        Software.sha256()
+    }
+
+    fn hmac_sha256(&self, key: &[u8]) -> Box<dyn hmac::Key> {
+        self.record(Call::HmacSha256);
+        Software.hmac_sha256(key)
+    }
+
+    #[cfg(feature = "aes-gcm")]
+    fn aes_gcm(&self, key: &[u8]) -> Box<dyn Aead> {
+        self.record(Call::AesGcm { key_len: key.len() });
+        Box::new(RecordingAead(self.clone(), Software.aes_gcm(key)))
+    }
+
+    #[cfg(feature = "chacha")]
+    fn chacha20_poly1305(&self, key: &[u8]) -> Box<dyn Aead> {
+        self.record(Call::ChaCha20Poly1305);
+        Box::new(RecordingAead(self.clone(), Software.chacha20_poly1305(key)))
+    }
+}
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+struct RecordingAead(Recording, Box<dyn Aead>);
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+impl Aead for RecordingAead {
+    fn seal(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; 16], aead::Error> {
+        self.0.record(Call::Seal { len: buffer.len() });
+        self.1.seal(nonce, aad, buffer)
+    }
+
+    fn open(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8; 16],
+    ) -> Result<(), aead::Error> {
+        self.0.record(Call::Open { len: buffer.len() });
+        self.1.open(nonce, aad, buffer, tag)
+    }
+}
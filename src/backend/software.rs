@@ -0,0 +1,105 @@
+use alloc::boxed::Box;
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use aead::{AeadInPlace, KeyInit};
+use hmac::{Hmac, Mac};
+use rustls::crypto::{hash, hmac as crypto_hmac};
+use sha2::{Digest, Sha256};
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+use super::Aead;
+
+pub(super) fn sha256() -> Box<dyn hash::Context> {
+    Box::new(Sha256Context(Sha256::new()))
+}
+
+pub(super) fn hmac_sha256(key: &[u8]) -> Box<dyn crypto_hmac::Key> {
+    Box::new(Sha256HmacKey(
+        <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap(),
+    ))
+}
+
+#[cfg(feature = "aes-gcm")]
+pub(super) fn aes_gcm(key: &[u8]) -> Box<dyn Aead> {
+    match key.len() {
+        16 => Box::new(Cipher(aes_gcm::Aes128Gcm::new_from_slice(key).unwrap())),
+        _ => Box::new(Cipher(aes_gcm::Aes256Gcm::new_from_slice(key).unwrap())),
+    }
+}
+
+#[cfg(feature = "chacha")]
+pub(super) fn chacha20_poly1305(key: &[u8]) -> Box<dyn Aead> {
+    Box::new(Cipher(
+        chacha20poly1305::ChaCha20Poly1305::new_from_slice(key).unwrap(),
+    ))
+}
+
+struct Sha256Context(Sha256);
+
+impl hash::Context for Sha256Context {
+    fn fork_finish(&self) -> hash::Output {
+        hash::Output::new(&self.0.clone().finalize()[..])
+    }
+
+    fn fork(&self) -> Box<dyn hash::Context> {
+        Box::new(Sha256Context(self.0.clone()))
+    }
+
+    fn finish(self: Box<Self>) -> hash::Output {
+        hash::Output::new(&self.0.finalize()[..])
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+}
+
+struct Sha256HmacKey(Hmac<Sha256>);
+
+impl crypto_hmac::Key for Sha256HmacKey {
+    fn sign_concat(&self, first: &[u8], middle: &[&[u8]], last: &[u8]) -> crypto_hmac::Tag {
+        let mut ctx = self.0.clone();
+        ctx.update(first);
+        for m in middle {
+            ctx.update(m);
+        }
+        ctx.update(last);
+        crypto_hmac::Tag::new(&ctx.finalize().into_bytes()[..])
+    }
+
+    fn tag_len(&self) -> usize {
+        Sha256::output_size()
+    }
+}
+
+/// A RustCrypto AEAD with the TLS nonce and tag sizes.
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+struct Cipher<C>(C);
+
+#[cfg(any(feature = "aes-gcm", feature = "chacha"))]
+impl<C> Aead for Cipher<C>
+where
+    C: AeadInPlace<NonceSize = aead::consts::U12, TagSize = aead::consts::U16> + Send + Sync,
+{
+    fn seal(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; 16], aead::Error> {
+        self.0
+            .encrypt_in_place_detached(nonce.into(), aad, buffer)
+            .map(Into::into)
+    }
+
+    fn open(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8; 16],
+    ) -> Result<(), aead::Error> {
+        self.0
+            .decrypt_in_place_detached(nonce.into(), aad, buffer, tag.into())
+    }
+}
@@ -0,0 +1,239 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use rustls::crypto::{CryptoProvider, SupportedKxGroup, WebPkiSupportedAlgorithms};
+use rustls::pki_types::SignatureVerificationAlgorithm;
+use rustls::{CipherSuite, NamedGroup, SignatureScheme, SupportedCipherSuite};
+
+use crate::kx;
+
+/// Builds a [`CryptoProvider`] with a subset of the algorithms of this build, e.g. to only
+/// offer TLS1.3 with X25519 to one server and a TLS1.2 RSA suite to another.
+///
+/// Each `with_*` method keeps only the listed algorithms, in the given order of
+/// preference; the lists that are not set keep everything [`crate::provider()`] offers.
+/// `with_kx_groups` can also pick `X25519MLKEM768`, which `provider()` leaves out with the
+/// `tls12` feature, as long as only TLS1.3 suites are selected.
+#[derive(Clone, Debug, Default)]
+pub struct ProviderBuilder {
+    cipher_suites: Option<Vec<CipherSuite>>,
+    kx_groups: Option<Vec<NamedGroup>>,
+    signature_schemes: Option<&'static SignatureSchemes>,
+}
+
+impl ProviderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cipher_suites(mut self, suites: &[CipherSuite]) -> Self {
+        self.cipher_suites = Some(suites.to_vec());
+        self
+    }
+
+    pub fn with_kx_groups(mut self, groups: &[NamedGroup]) -> Self {
+        self.kx_groups = Some(groups.to_vec());
+        self
+    }
+
+    /// Restricts the signature schemes accepted in the handshake. Certificate chains are
+    /// still verified with every algorithm of the build.
+    pub fn with_signature_schemes(mut self, schemes: &'static SignatureSchemes) -> Self {
+        self.signature_schemes = Some(schemes);
+        self
+    }
+
+    /// Fails if an algorithm isn't compiled in, is listed twice, or if the selection
+    /// can't complete a handshake.
+    pub fn build(self) -> Result<CryptoProvider, rustls::Error> {
+        let mut provider = crate::provider();
+
+        if let Some(suites) = &self.cipher_suites {
+            provider.cipher_suites = select(
+                &provider.cipher_suites,
+                suites,
+                |suite| suite.suite(),
+                "cipher suite",
+            )?;
+        }
+        if let Some(groups) = &self.kx_groups {
+            provider.kx_groups = select(
+                kx::ALL_KX_GROUPS,
+                groups,
+                |group| group.name(),
+                "key exchange group",
+            )?;
+        }
+
+        let algorithms = provider.signature_verification_algorithms;
+        let mapping = match self.signature_schemes {
+            Some(schemes) => schemes.mapping(algorithms.mapping)?,
+            None => algorithms.mapping,
+        };
+        let schemes: Vec<SignatureScheme> = mapping.iter().map(|m| m.0).collect();
+        check(&provider.cipher_suites, &provider.kx_groups, &schemes)?;
+
+        provider.signature_verification_algorithms = WebPkiSupportedAlgorithms {
+            all: algorithms.all,
+            mapping,
+        };
+        Ok(provider)
+    }
+}
+
+type Mapping = (
+    SignatureScheme,
+    &'static [&'static dyn SignatureVerificationAlgorithm],
+);
+
+/// A list of signature schemes for [`ProviderBuilder::with_signature_schemes`], in order of
+/// preference.
+///
+/// `CryptoProvider` only takes a `'static` scheme mapping, so the list is declared as a
+/// `static` that holds it: the first [`ProviderBuilder::build`] allocates the mapping, and
+/// every later one shares it.
+///
+/// ```
+/// # use no_std_embedded_demo::{ProviderBuilder, SignatureSchemes};
+/// # use rustls::SignatureScheme;
+/// static ECDSA: SignatureSchemes = SignatureSchemes::new(&[
+///     SignatureScheme::ECDSA_NISTP256_SHA256,
+///     SignatureScheme::ECDSA_NISTP384_SHA384,
+/// ]);
+///
+/// let provider = ProviderBuilder::new().with_signature_schemes(&ECDSA).build();
+/// ```
+#[derive(Debug)]
+pub struct SignatureSchemes {
+    schemes: &'static [SignatureScheme],
+    mapping: AtomicPtr<Vec<Mapping>>,
+}
+
+impl SignatureSchemes {
+    pub const fn new(schemes: &'static [SignatureScheme]) -> Self {
+        Self {
+            schemes,
+            mapping: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// The mapping of the schemes, picked out of `available` by the first call.
+    fn mapping(&'static self, available: &[Mapping]) -> Result<&'static [Mapping], rustls::Error> {
+        let kept = self.mapping.load(Ordering::Acquire);
+        if !kept.is_null() {
+            // SAFETY: a non-null pointer comes from `Box::into_raw` below and is never freed
+            return Ok(unsafe { &*kept });
+        }
+
+        let new = Box::into_raw(Box::new(select(
+            available,
+            self.schemes,
+            |m| m.0,
+            "signature scheme",
+        )?));
+        match self.mapping.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            // SAFETY: `new` is now stored, and never freed
+            Ok(_) => Ok(unsafe { &*new }),
+            Err(kept) => {
+                // SAFETY: another build stored its mapping first, so `new` has no other owner
+                drop(unsafe { Box::from_raw(new) });
+                // SAFETY: as above
+                Ok(unsafe { &*kept })
+            }
+        }
+    }
+}
+
+/// Picks the `wanted` items out of `available`, in the order of `wanted`.
+fn select<T: Clone, N: Debug + PartialEq>(
+    available: &[T],
+    wanted: &[N],
+    name: impl Fn(&T) -> N,
+    what: &str,
+) -> Result<Vec<T>, rustls::Error> {
+    let mut selected = Vec::with_capacity(wanted.len());
+    for (i, want) in wanted.iter().enumerate() {
+        if wanted[..i].contains(want) {
+            return Err(rustls::Error::General(format!(
+                "{what} {want:?} is listed twice"
+            )));
+        }
+        let item = available
+            .iter()
+            .find(|item| name(item) == *want)
+            .ok_or_else(|| {
+                rustls::Error::General(format!("{what} {want:?} is not part of this build"))
+            })?;
+        selected.push(item.clone());
+    }
+    Ok(selected)
+}
+
+/// Every cipher suite must be able to authenticate the server with one of `schemes`, and
+/// TLS1.2 suites can't be combined with TLS1.3-only groups: a server would pick them for
+/// TLS1.2 as well.
+fn check(
+    suites: &[SupportedCipherSuite],
+    groups: &[&'static dyn SupportedKxGroup],
+    schemes: &[SignatureScheme],
+) -> Result<(), rustls::Error> {
+    if suites.is_empty() {
+        return Err(rustls::Error::General("no cipher suite selected".into()));
+    }
+    if groups.is_empty() {
+        return Err(rustls::Error::General(
+            "no key exchange group selected".into(),
+        ));
+    }
+
+    for suite in suites {
+        let usable = match suite {
+            SupportedCipherSuite::Tls13(_) => schemes.iter().any(supported_in_tls13),
+            #[cfg(feature = "tls12")]
+            SupportedCipherSuite::Tls12(tls12) => {
+                if let Some(group) = groups
+                    .iter()
+                    .find(|group| kx::TLS13_ONLY_GROUPS.contains(&group.name()))
+                {
+                    return Err(rustls::Error::General(format!(
+                        "{:?} can't be used with the TLS1.3-only key exchange group {:?}",
+                        suite.suite(),
+                        group.name()
+                    )));
+                }
+                tls12.sign.iter().any(|scheme| schemes.contains(scheme))
+            }
+        };
+        if !usable {
+            return Err(rustls::Error::General(format!(
+                "{:?} can't be used with any of the selected signature schemes",
+                suite.suite()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// RFC8446 section 4.2.3 drops PKCS#1 v1.5 and SHA-1 from TLS1.3 handshake signatures.
+/// rustls keeps its own version of this check private.
+fn supported_in_tls13(scheme: &SignatureScheme) -> bool {
+    matches!(
+        scheme,
+        SignatureScheme::ECDSA_NISTP521_SHA512
+            | SignatureScheme::ECDSA_NISTP384_SHA384
+            | SignatureScheme::ECDSA_NISTP256_SHA256
+            | SignatureScheme::RSA_PSS_SHA512
+            | SignatureScheme::RSA_PSS_SHA384
+            | SignatureScheme::RSA_PSS_SHA256
+            | SignatureScheme::ED25519
+    )
+}
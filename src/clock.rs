@@ -0,0 +1,115 @@
+use core::sync::atomic::{fence, AtomicU32, Ordering};
+use core::time::Duration;
+
+use rustls::pki_types::UnixTime;
+
+/// A wall clock that the network task sets from a time server and that `TimeProvider`s
+/// read at any point of a handshake, without locking or blocking.
+///
+/// It stores the Unix time of the last synchronization, how far off it can be, and the
+/// reading of a monotonic clock (e.g. `embassy_time::Instant`) at that moment, and
+/// extrapolates from there. Cortex-M4 has no 64-bit atomics, so the times are split in
+/// 32-bit halves behind a sequence lock.
+#[derive(Debug)]
+pub struct Clock {
+    /// Odd while `set` runs, zero until the first `set`.
+    seq: AtomicU32,
+    unix_hi: AtomicU32,
+    unix_lo: AtomicU32,
+    /// Microseconds.
+    radius: AtomicU32,
+    at_hi: AtomicU32,
+    at_lo: AtomicU32,
+}
+
+impl Clock {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            unix_hi: AtomicU32::new(0),
+            unix_lo: AtomicU32::new(0),
+            radius: AtomicU32::new(0),
+            at_hi: AtomicU32::new(0),
+            at_lo: AtomicU32::new(0),
+        }
+    }
+
+    /// Records that the Unix time was `unix`, give or take `radius`, when the monotonic clock
+    /// read `at` microseconds. The radius is capped at `u32::MAX` microseconds (71 minutes).
+    ///
+    /// Returns `false` without changing anything if another `set` is in progress.
+    pub fn set(&self, unix: Duration, radius: Duration, at: u64) -> bool {
+        let seq = self.seq.load(Ordering::Relaxed);
+        if seq % 2 == 1 {
+            return false;
+        }
+        let odd = seq.wrapping_add(1);
+        if self
+            .seq
+            .compare_exchange(seq, odd, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        fence(Ordering::Release);
+
+        let unix = unix.as_micros() as u64;
+        self.unix_hi.store((unix >> 32) as u32, Ordering::Relaxed);
+        self.unix_lo.store(unix as u32, Ordering::Relaxed);
+        let radius = u32::try_from(radius.as_micros()).unwrap_or(u32::MAX);
+        self.radius.store(radius, Ordering::Relaxed);
+        self.at_hi.store((at >> 32) as u32, Ordering::Relaxed);
+        self.at_lo.store(at as u32, Ordering::Relaxed);
+
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+        true
+    }
+
+    /// The Unix time when the monotonic clock reads `now` microseconds.
+    ///
+    /// `None` until the first [`Self::set`], or if `set` keeps running concurrently
+    /// (e.g. this is called from an interrupt that preempted it).
+    pub fn now(&self, now: u64) -> Option<UnixTime> {
+        self.now_with_radius(now).map(|(time, _)| time)
+    }
+
+    /// [`Self::now`], and how far off it can be: the radius passed to [`Self::set`]
+    /// (the drift of the monotonic clock since isn't included).
+    pub fn now_with_radius(&self, now: u64) -> Option<(UnixTime, Duration)> {
+        for _ in 0..READ_ATTEMPTS {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq == 0 {
+                return None;
+            }
+            if seq % 2 == 1 {
+                continue;
+            }
+
+            let unix = u64::from(self.unix_hi.load(Ordering::Relaxed)) << 32
+                | u64::from(self.unix_lo.load(Ordering::Relaxed));
+            let radius = self.radius.load(Ordering::Relaxed);
+            let at = u64::from(self.at_hi.load(Ordering::Relaxed)) << 32
+                | u64::from(self.at_lo.load(Ordering::Relaxed));
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) != seq {
+                continue;
+            }
+
+            // a monotonic clock never reads earlier than a previous `set`, but don't trust it
+            let elapsed = now.saturating_sub(at);
+            let time =
+                UnixTime::since_unix_epoch(Duration::from_micros(unix.saturating_add(elapsed)));
+            return Some((time, Duration::from_micros(radius.into())));
+        }
+        None
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const READ_ATTEMPTS: usize = 4;
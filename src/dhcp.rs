@@ -0,0 +1,222 @@
+//! The NTP servers of a DHCP lease: option 42 of the server's DHCPACK (RFC2132 section 8.3).
+//!
+//! embassy-net 0.4 runs its own DHCP client and only exposes the address, the gateway and
+//! the DNS servers of the lease, so this only parses the DHCPACK; the caller gets it from
+//! its own DHCP client, e.g. smoltcp's `dhcpv4::Socket` with option 42 in its parameter
+//! request list and a receive packet buffer, whose `Config::packet` is the DHCPACK.
+
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+use core::ops::Range;
+
+/// BOOTP's fixed fields, then the magic cookie.
+const OPTIONS: usize = 240;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const BOOTREPLY: u8 = 2;
+
+const PAD: u8 = 0;
+const NTP_SERVERS: u8 = 42;
+const OVERLOAD: u8 = 52;
+const MESSAGE_TYPE: u8 = 53;
+const END: u8 = 255;
+const DHCPACK: u8 = 5;
+
+/// The `sname` and `file` fields, which carry more options with [`OVERLOAD`].
+const SNAME: Range<usize> = 44..108;
+const FILE: Range<usize> = 108..236;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Too short, without the magic cookie, or an option runs past the end.
+    Malformed,
+    /// Not a DHCPACK from a server.
+    NotAck,
+}
+
+/// The NTP servers of a DHCPACK, in the server's order of preference; empty without
+/// option 42.
+pub fn ntp_servers(packet: &[u8]) -> Result<Vec<Ipv4Addr>, Error> {
+    if packet.len() < OPTIONS || packet[236..OPTIONS] != MAGIC_COOKIE {
+        return Err(Error::Malformed);
+    }
+
+    let mut options = Options::default();
+    options.parse(&packet[OPTIONS..])?;
+    // RFC2131 section 4.1: `file` is read before `sname`
+    let overload = options.overload;
+    if overload & 1 != 0 {
+        options.parse(&packet[FILE])?;
+    }
+    if overload & 2 != 0 {
+        options.parse(&packet[SNAME])?;
+    }
+
+    if packet[0] != BOOTREPLY || options.message_type != Some(DHCPACK) {
+        return Err(Error::NotAck);
+    }
+    if options.ntp_servers.len() % 4 != 0 {
+        return Err(Error::Malformed);
+    }
+    Ok(options
+        .ntp_servers
+        .chunks_exact(4)
+        .map(|a| Ipv4Addr::new(a[0], a[1], a[2], a[3]))
+        .collect())
+}
+
+/// The options that [`ntp_servers`] needs.
+#[derive(Default)]
+struct Options {
+    /// RFC3396: an option that appears more than once is the concatenation of its parts.
+    ntp_servers: Vec<u8>,
+    message_type: Option<u8>,
+    overload: u8,
+}
+
+impl Options {
+    fn parse(&mut self, mut area: &[u8]) -> Result<(), Error> {
+        while let [code, rest @ ..] = area {
+            match *code {
+                PAD => area = rest,
+                END => break,
+                code => {
+                    let (&len, rest) = rest.split_first().ok_or(Error::Malformed)?;
+                    let (data, rest) = rest.split_at_checked(len.into()).ok_or(Error::Malformed)?;
+                    match (code, data) {
+                        (NTP_SERVERS, data) => self.ntp_servers.extend_from_slice(data),
+                        (MESSAGE_TYPE, &[typ]) => self.message_type = Some(typ),
+                        (OVERLOAD, &[fields]) => self.overload = fields,
+                        _ => {}
+                    }
+                    area = rest;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DHCPACK with `options` after the message type.
+    fn ack(options: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0; OPTIONS];
+        packet[..4].copy_from_slice(&[BOOTREPLY, 1, 6, 0]);
+        packet[236..].copy_from_slice(&MAGIC_COOKIE);
+        packet.extend([MESSAGE_TYPE, 1, DHCPACK]);
+        packet.extend(options);
+        packet.push(END);
+        packet
+    }
+
+    #[test]
+    fn servers_in_order() {
+        let packet = ack(&[
+            1,
+            4,
+            255,
+            255,
+            255,
+            0, // subnet mask
+            PAD,
+            NTP_SERVERS,
+            8,
+            192,
+            168,
+            1,
+            1,
+            10,
+            0,
+            0,
+            123,
+        ]);
+        assert_eq!(
+            ntp_servers(&packet),
+            Ok(vec![
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(10, 0, 0, 123)
+            ])
+        );
+    }
+
+    #[test]
+    fn without_option_42() {
+        assert_eq!(ntp_servers(&ack(&[3, 4, 192, 168, 1, 1])), Ok(vec![]));
+    }
+
+    #[test]
+    fn split_option() {
+        // RFC3396: the parts are concatenated, even across the address boundary
+        let packet = ack(&[
+            NTP_SERVERS,
+            6,
+            192,
+            168,
+            1,
+            1,
+            10,
+            0,
+            NTP_SERVERS,
+            2,
+            0,
+            123,
+        ]);
+        assert_eq!(
+            ntp_servers(&packet),
+            Ok(vec![
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(10, 0, 0, 123)
+            ])
+        );
+    }
+
+    #[test]
+    fn overloaded_file_and_sname() {
+        let mut packet = ack(&[OVERLOAD, 1, 3, NTP_SERVERS, 4, 10, 0, 0, 1]);
+        packet[FILE][..7].copy_from_slice(&[NTP_SERVERS, 4, 10, 0, 0, 2, END]);
+        packet[SNAME][..7].copy_from_slice(&[NTP_SERVERS, 4, 10, 0, 0, 3, END]);
+        assert_eq!(
+            ntp_servers(&packet),
+            Ok(vec![
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 2),
+                Ipv4Addr::new(10, 0, 0, 3)
+            ])
+        );
+        // without the overload option, both fields are just names
+        packet[OPTIONS + 5] = 0;
+        assert_eq!(ntp_servers(&packet), Ok(vec![Ipv4Addr::new(10, 0, 0, 1)]));
+    }
+
+    #[test]
+    fn not_an_ack() {
+        let servers = [NTP_SERVERS, 4, 10, 0, 0, 1];
+        // an offer
+        let mut packet = ack(&servers);
+        packet[OPTIONS + 2] = 2;
+        assert_eq!(ntp_servers(&packet), Err(Error::NotAck));
+        // a request from a client
+        let mut packet = ack(&servers);
+        packet[0] = 1;
+        assert_eq!(ntp_servers(&packet), Err(Error::NotAck));
+    }
+
+    #[test]
+    fn malformed() {
+        let packet = ack(&[NTP_SERVERS, 4, 10, 0, 0, 1]);
+        let mut no_cookie = packet.clone();
+        no_cookie[236] = 0;
+        let short_address = ack(&[NTP_SERVERS, 3, 10, 0, 0]);
+        let past_the_end = &packet[..packet.len() - 3];
+        for packet in [
+            &packet[..OPTIONS - 1],
+            &no_cookie,
+            &short_address,
+            past_the_end,
+        ] {
+            assert_eq!(ntp_servers(packet), Err(Error::Malformed));
+        }
+    }
+}
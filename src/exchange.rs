@@ -0,0 +1,312 @@
+//! The request and reply loop of the time clients, over any [`Transport`]: embassy-net on
+//! the board, and `std::net::UdpSocket` for NTS on hosts and against a stand-in server in
+//! the tests.
+
+use core::time::Duration;
+
+#[cfg(feature = "nts")]
+use crate::nts::{self, NtsSession};
+#[cfg(all(feature = "roughtime", any(feature = "embassy", test)))]
+use crate::roughtime;
+#[cfg(any(feature = "embassy", test))]
+use crate::sntp::Request;
+use crate::sntp::{self, Sample};
+
+/// A socket talking to one server, and the monotonic clock in microseconds.
+pub(crate) trait Transport {
+    type Error: From<sntp::Error>;
+
+    fn now(&self) -> u64;
+
+    async fn send(&self, packet: &[u8]) -> Result<(), Self::Error>;
+
+    /// The next datagram, or a timeout error when none arrives before `deadline`.
+    async fn recv(&self, buffer: &mut [u8], deadline: u64) -> Result<usize, Self::Error>;
+}
+
+/// Sends one SNTP request with `nonce`, and waits up to `timeout` for its reply.
+///
+/// Datagrams that aren't a server reply to this request are dropped, so a stray or forged
+/// packet can't end the query early; errors of a reply that echoes the request are returned.
+#[cfg(any(feature = "embassy", test))]
+pub(crate) async fn query<T: Transport>(
+    server: &T,
+    nonce: [u8; 8],
+    timeout: Duration,
+) -> Result<Sample, T::Error> {
+    let request = Request::new(nonce, server.now());
+    server.send(&request.packet()).await?;
+
+    let deadline = server.now() + timeout.as_micros() as u64;
+    let mut reply = [0; sntp::PACKET_LEN];
+    loop {
+        let len = server.recv(&mut reply, deadline).await?;
+        match request.reply(&reply[..len], server.now()) {
+            // not NTP, a late reply to a previous attempt, or a forged one
+            Err(
+                sntp::Error::Truncated | sntp::Error::NotServerReply | sntp::Error::OriginMismatch,
+            ) => continue,
+            result => return result.map_err(Into::into),
+        }
+    }
+}
+
+/// Sends one Roughtime request with `nonce`, and waits up to `timeout` for its reply.
+///
+/// Like [`query`], it drops datagrams that aren't a reply signed for this request.
+#[cfg(all(feature = "roughtime", any(feature = "embassy", test)))]
+pub(crate) async fn query_roughtime<T: Transport>(
+    server: &T,
+    public_key: &[u8; 32],
+    nonce: [u8; roughtime::NONCE_LEN],
+    timeout: Duration,
+) -> Result<roughtime::Sample, T::Error>
+where
+    T::Error: From<roughtime::Error>,
+{
+    let request = roughtime::Request::new(nonce, server.now());
+    server.send(&request.packet()).await?;
+
+    let deadline = server.now() + timeout.as_micros() as u64;
+    let mut reply = [0; roughtime::REQUEST_LEN];
+    loop {
+        let len = server.recv(&mut reply, deadline).await?;
+        match request.reply(&reply[..len], public_key, server.now()) {
+            // not Roughtime, a late reply to a previous attempt, or a forged one
+            Err(
+                roughtime::Error::Malformed
+                | roughtime::Error::NonceMismatch
+                | roughtime::Error::BadDelegation
+                | roughtime::Error::BadSignature,
+            ) => continue,
+            result => return result.map_err(Into::into),
+        }
+    }
+}
+
+/// Sends the next request of `session`, and waits up to `timeout` for its reply.
+///
+/// Like [`query`], it drops datagrams that aren't a reply to this request. A reply that
+/// echoes it but isn't authenticated ends the query; the next request asks for its cookie.
+#[cfg(feature = "nts")]
+pub(crate) async fn query_nts<T: Transport>(
+    server: &T,
+    session: &mut NtsSession,
+    timeout: Duration,
+) -> Result<Sample, T::Error>
+where
+    T::Error: From<nts::Error>,
+{
+    let request = session.request(server.now())?;
+    server.send(request.packet()).await?;
+
+    let deadline = server.now() + timeout.as_micros() as u64;
+    let mut reply = [0; nts::PACKET_LEN];
+    loop {
+        let len = server.recv(&mut reply, deadline).await?;
+        match session.reply(&request, &reply[..len], server.now()) {
+            // not NTP, a late reply to a previous attempt, or a forged one
+            Err(nts::Error::Sntp(
+                sntp::Error::Truncated | sntp::Error::NotServerReply | sntp::Error::OriginMismatch,
+            )) => continue,
+            result => return result.map_err(Into::into),
+        }
+    }
+}
+
+#[cfg(any(all(feature = "nts", feature = "std"), test))]
+pub(crate) use blocking::block_on;
+
+#[cfg(any(all(feature = "nts", feature = "std"), test))]
+mod blocking {
+    use alloc::sync::Arc;
+    use alloc::task::Wake;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Polls `future` to completion; the `std` sockets block instead of returning `Pending`.
+    pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(Noop));
+        let mut context = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Timeout,
+        Sntp(sntp::Error),
+        #[cfg(feature = "roughtime")]
+        Roughtime(roughtime::Error),
+    }
+
+    impl From<sntp::Error> for Error {
+        fn from(e: sntp::Error) -> Self {
+            Self::Sntp(e)
+        }
+    }
+
+    #[cfg(feature = "roughtime")]
+    impl From<roughtime::Error> for Error {
+        fn from(e: roughtime::Error) -> Self {
+            Self::Roughtime(e)
+        }
+    }
+
+    /// A socket connected to the stand-in server, and a clock that starts with it.
+    struct Local(UdpSocket, Instant);
+
+    impl Transport for Local {
+        type Error = Error;
+
+        fn now(&self) -> u64 {
+            self.1.elapsed().as_micros() as u64
+        }
+
+        async fn send(&self, packet: &[u8]) -> Result<(), Error> {
+            self.0.send(packet).unwrap();
+            Ok(())
+        }
+
+        async fn recv(&self, buffer: &mut [u8], deadline: u64) -> Result<usize, Error> {
+            let remaining = deadline.saturating_sub(self.now()).max(1);
+            self.0
+                .set_read_timeout(Some(Duration::from_micros(remaining)))
+                .unwrap();
+            self.0.recv(buffer).map_err(|_| Error::Timeout)
+        }
+    }
+
+    /// A client socket, and a server socket that it's connected to.
+    fn sockets() -> (UdpSocket, UdpSocket) {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn query_local_server() {
+        let (client, server) = sockets();
+        let stand_in = thread::spawn(move || {
+            let mut request = [0; 64];
+            let (len, from) = server.recv_from(&mut request).unwrap();
+            assert_eq!((len, request[0]), (sntp::PACKET_LEN, 0x23));
+
+            // stratum 1, received and sent at 2024-06-01T00:00:00Z
+            let mut reply = [0; sntp::PACKET_LEN];
+            reply[..2].copy_from_slice(&[0x24, 1]);
+            let now = (1_717_200_000u64 + 2_208_988_800) << 32;
+            reply[32..40].copy_from_slice(&now.to_be_bytes());
+            reply[40..48].copy_from_slice(&now.to_be_bytes());
+            // junk, a late reply to an earlier request and the request itself first, which
+            // are skipped
+            server.send_to(b"junk", from).unwrap();
+            server.send_to(&reply, from).unwrap();
+            server.send_to(&request[..len], from).unwrap();
+            reply[24..32].copy_from_slice(&request[40..48]);
+            server.send_to(&reply, from).unwrap();
+        });
+
+        let local = Local(client, Instant::now());
+        let sample = block_on(query(&local, *b"01234567", Duration::from_secs(2))).unwrap();
+        stand_in.join().unwrap();
+        assert_eq!(sample.stratum, 1);
+        assert_eq!(sample.unix.as_secs(), 1_717_200_000);
+        assert!(sample.delay < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn query_returns_kiss_of_death() {
+        let (client, server) = sockets();
+        let stand_in = thread::spawn(move || {
+            let mut request = [0; 64];
+            let (_, from) = server.recv_from(&mut request).unwrap();
+            let mut reply = [0; sntp::PACKET_LEN];
+            reply[0] = 0x24;
+            reply[12..16].copy_from_slice(b"RATE");
+            reply[24..32].copy_from_slice(&request[40..48]);
+            server.send_to(&reply, from).unwrap();
+        });
+
+        let local = Local(client, Instant::now());
+        let result = block_on(query(&local, *b"01234567", Duration::from_secs(2)));
+        stand_in.join().unwrap();
+        assert_eq!(result, Err(Error::Sntp(sntp::Error::KissOfDeath(*b"RATE"))));
+    }
+
+    #[test]
+    fn query_times_out() {
+        let (client, _server) = sockets();
+        let local = Local(client, Instant::now());
+        let result = block_on(query(&local, *b"01234567", Duration::from_millis(50)));
+        assert_eq!(result, Err(Error::Timeout));
+    }
+
+    #[cfg(feature = "roughtime")]
+    #[test]
+    fn query_roughtime_local_server() {
+        let reply = include_bytes!("../tests/data/roughtime/reply");
+        let (client, server) = sockets();
+        let stand_in = thread::spawn(move || {
+            let mut request = [0; roughtime::REQUEST_LEN];
+            let (len, from) = server.recv_from(&mut request).unwrap();
+            assert_eq!(len, roughtime::REQUEST_LEN);
+            // junk and a forged reply first, which are skipped
+            server.send_to(b"not a Roughtime message", from).unwrap();
+            let mut forged = *reply;
+            forged[reply.len() - 1] ^= 1;
+            server.send_to(&forged, from).unwrap();
+            server.send_to(reply, from).unwrap();
+        });
+
+        let local = Local(client, Instant::now());
+        let public_key = include_bytes!("../tests/data/roughtime/public_key");
+        // the nonce of the generated reply
+        let nonce = [12; roughtime::NONCE_LEN];
+        let timeout = Duration::from_secs(2);
+        let sample = block_on(query_roughtime(&local, public_key, nonce, timeout)).unwrap();
+        stand_in.join().unwrap();
+        assert_eq!(sample.radius.as_secs(), 1);
+    }
+
+    #[cfg(feature = "roughtime")]
+    #[test]
+    fn query_roughtime_skips_junk() {
+        let (client, server) = sockets();
+        let stand_in = thread::spawn(move || {
+            let mut request = [0; roughtime::REQUEST_LEN];
+            let (len, from) = server.recv_from(&mut request).unwrap();
+            assert_eq!(len, roughtime::REQUEST_LEN);
+            server.send_to(b"not a Roughtime message", from).unwrap();
+            server.send_to(&request, from).unwrap();
+        });
+
+        let local = Local(client, Instant::now());
+        let nonce = [7; roughtime::NONCE_LEN];
+        let timeout = Duration::from_millis(200);
+        let result = block_on(query_roughtime(&local, &[0; 32], nonce, timeout));
+        stand_in.join().unwrap();
+        assert_eq!(result, Err(Error::Timeout));
+    }
+}
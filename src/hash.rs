@@ -1,17 +1,22 @@
 use alloc::boxed::Box;
 
 use rustls::crypto::hash;
+#[cfg(feature = "aes-gcm")]
 use sha2::Digest;
 
+use crate::backend::backend;
+
 pub struct Sha256;
 
 impl hash::Hash for Sha256 {
     fn start(&self) -> Box<dyn hash::Context> {
-        Box::new(Sha256Context(sha2::Sha256::new()))
+        backend().sha256()
     }
 
     fn hash(&self, data: &[u8]) -> hash::Output {
-        hash::Output::new(&sha2::Sha256::digest(data)[..])
+        let mut ctx = self.start();
+        ctx.update(data);
+        ctx.finish()
     }
 
     fn algorithm(&self) -> hash::HashAlgorithm {
@@ -23,15 +28,39 @@ impl hash::Hash for Sha256 {
     }
 }
 
-struct Sha256Context(sha2::Sha256);
+#[cfg(feature = "aes-gcm")]
+pub struct Sha384;
+
+#[cfg(feature = "aes-gcm")]
+impl hash::Hash for Sha384 {
+    fn start(&self) -> Box<dyn hash::Context> {
+        Box::new(Sha384Context(sha2::Sha384::new()))
+    }
+
+    fn hash(&self, data: &[u8]) -> hash::Output {
+        hash::Output::new(&sha2::Sha384::digest(data)[..])
+    }
+
+    fn algorithm(&self) -> hash::HashAlgorithm {
+        hash::HashAlgorithm::SHA384
+    }
+
+    fn output_len(&self) -> usize {
+        48
+    }
+}
+
+#[cfg(feature = "aes-gcm")]
+struct Sha384Context(sha2::Sha384);
 
-impl hash::Context for Sha256Context {
+#[cfg(feature = "aes-gcm")]
+impl hash::Context for Sha384Context {
     fn fork_finish(&self) -> hash::Output {
         hash::Output::new(&self.0.clone().finalize()[..])
     }
 
     fn fork(&self) -> Box<dyn hash::Context> {
-        Box::new(Sha256Context(self.0.clone()))
+        Box::new(Sha384Context(self.0.clone()))
     }
 
     fn finish(self: Box<Self>) -> hash::Output {
@@ -1,24 +1,44 @@
 use alloc::boxed::Box;
 
+#[cfg(feature = "aes-gcm")]
 use hmac::{Hmac, Mac};
 use rustls::crypto;
-use sha2::{Digest, Sha256};
+#[cfg(feature = "aes-gcm")]
+use sha2::{Digest, Sha384};
+
+use crate::backend::backend;
 
 pub struct Sha256Hmac;
 
 impl crypto::hmac::Hmac for Sha256Hmac {
     fn with_key(&self, key: &[u8]) -> Box<dyn crypto::hmac::Key> {
-        Box::new(Sha256HmacKey(Hmac::<Sha256>::new_from_slice(key).unwrap()))
+        backend().hmac_sha256(key)
+    }
+
+    fn hash_output_len(&self) -> usize {
+        32
+    }
+}
+
+#[cfg(feature = "aes-gcm")]
+pub struct Sha384Hmac;
+
+#[cfg(feature = "aes-gcm")]
+impl crypto::hmac::Hmac for Sha384Hmac {
+    fn with_key(&self, key: &[u8]) -> Box<dyn crypto::hmac::Key> {
+        Box::new(Sha384HmacKey(Hmac::<Sha384>::new_from_slice(key).unwrap()))
     }
 
     fn hash_output_len(&self) -> usize {
-        Sha256::output_size()
+        Sha384::output_size()
     }
 }
 
-struct Sha256HmacKey(Hmac<Sha256>);
+#[cfg(feature = "aes-gcm")]
+struct Sha384HmacKey(Hmac<Sha384>);
 
-impl crypto::hmac::Key for Sha256HmacKey {
+#[cfg(feature = "aes-gcm")]
+impl crypto::hmac::Key for Sha384HmacKey {
     fn sign_concat(&self, first: &[u8], middle: &[&[u8]], last: &[u8]) -> crypto::hmac::Tag {
         let mut ctx = self.0.clone();
         ctx.update(first);
@@ -30,6 +50,6 @@ impl crypto::hmac::Key for Sha256HmacKey {
     }
 
     fn tag_len(&self) -> usize {
-        Sha256::output_size()
+        Sha384::output_size()
     }
 }
@@ -0,0 +1,150 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+
+use hpke::aead::{AeadCtxR, AeadCtxS, ChaCha20Poly1305};
+use hpke::kdf::HkdfSha256;
+use hpke::kem::X25519HkdfSha256;
+use hpke::{Deserializable, Kem as _, OpModeR, OpModeS, Serializable};
+use rustls::crypto::hpke::{
+    EncapsulatedSecret, Hpke, HpkeOpener, HpkePrivateKey, HpkePublicKey, HpkeSealer, HpkeSuite,
+};
+// rustls 0.23 only exposes the HPKE algorithm identifiers through `internal`
+use rustls::internal::msgs::enums::{HpkeAead, HpkeKdf, HpkeKem};
+use rustls::internal::msgs::handshake::HpkeSymmetricCipherSuite;
+
+pub static HPKE_SUITES: &[&dyn Hpke] = &[&DhKemX25519Sha256ChaCha20Poly1305];
+
+type Kem = X25519HkdfSha256;
+type PublicKey = <Kem as hpke::Kem>::PublicKey;
+type PrivateKey = <Kem as hpke::Kem>::PrivateKey;
+type EncappedKey = <Kem as hpke::Kem>::EncappedKey;
+
+#[derive(Debug)]
+struct DhKemX25519Sha256ChaCha20Poly1305;
+
+impl Hpke for DhKemX25519Sha256ChaCha20Poly1305 {
+    fn seal(
+        &self,
+        info: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        pub_key: &HpkePublicKey,
+    ) -> Result<(EncapsulatedSecret, Vec<u8>), rustls::Error> {
+        let (enc, ciphertext) = hpke::single_shot_seal::<ChaCha20Poly1305, HkdfSha256, Kem, _>(
+            &OpModeS::Base,
+            &PublicKey::from_bytes(&pub_key.0).map_err(hpke_error)?,
+            info,
+            plaintext,
+            aad,
+            &mut rand_core::OsRng,
+        )
+        .map_err(hpke_error)?;
+        Ok((EncapsulatedSecret(enc.to_bytes().to_vec()), ciphertext))
+    }
+
+    fn setup_sealer(
+        &self,
+        info: &[u8],
+        pub_key: &HpkePublicKey,
+    ) -> Result<(EncapsulatedSecret, Box<dyn HpkeSealer + 'static>), rustls::Error> {
+        let (enc, context) = hpke::setup_sender::<ChaCha20Poly1305, HkdfSha256, Kem, _>(
+            &OpModeS::Base,
+            &PublicKey::from_bytes(&pub_key.0).map_err(hpke_error)?,
+            info,
+            &mut rand_core::OsRng,
+        )
+        .map_err(hpke_error)?;
+        Ok((
+            EncapsulatedSecret(enc.to_bytes().to_vec()),
+            Box::new(Sealer(context)),
+        ))
+    }
+
+    fn open(
+        &self,
+        enc: &EncapsulatedSecret,
+        info: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        secret_key: &HpkePrivateKey,
+    ) -> Result<Vec<u8>, rustls::Error> {
+        hpke::single_shot_open::<ChaCha20Poly1305, HkdfSha256, Kem>(
+            &OpModeR::Base,
+            &PrivateKey::from_bytes(secret_key.secret_bytes()).map_err(hpke_error)?,
+            &EncappedKey::from_bytes(&enc.0).map_err(hpke_error)?,
+            info,
+            ciphertext,
+            aad,
+        )
+        .map_err(hpke_error)
+    }
+
+    fn setup_opener(
+        &self,
+        enc: &EncapsulatedSecret,
+        info: &[u8],
+        secret_key: &HpkePrivateKey,
+    ) -> Result<Box<dyn HpkeOpener + 'static>, rustls::Error> {
+        let context = hpke::setup_receiver::<ChaCha20Poly1305, HkdfSha256, Kem>(
+            &OpModeR::Base,
+            &PrivateKey::from_bytes(secret_key.secret_bytes()).map_err(hpke_error)?,
+            &EncappedKey::from_bytes(&enc.0).map_err(hpke_error)?,
+            info,
+        )
+        .map_err(hpke_error)?;
+        Ok(Box::new(Opener(context)))
+    }
+
+    fn generate_key_pair(&self) -> Result<(HpkePublicKey, HpkePrivateKey), rustls::Error> {
+        let (private_key, public_key) = Kem::gen_keypair(&mut rand_core::OsRng);
+        Ok((
+            HpkePublicKey(public_key.to_bytes().to_vec()),
+            HpkePrivateKey::from(private_key.to_bytes().to_vec()),
+        ))
+    }
+
+    fn suite(&self) -> HpkeSuite {
+        HpkeSuite {
+            kem: HpkeKem::DHKEM_X25519_HKDF_SHA256,
+            sym: HpkeSymmetricCipherSuite {
+                kdf_id: HpkeKdf::HKDF_SHA256,
+                aead_id: HpkeAead::CHACHA20_POLY_1305,
+            },
+        }
+    }
+}
+
+struct Sealer(AeadCtxS<ChaCha20Poly1305, HkdfSha256, Kem>);
+
+impl HpkeSealer for Sealer {
+    fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.0.seal(plaintext, aad).map_err(hpke_error)
+    }
+}
+
+// the contexts hold the keys, so they don't implement `Debug`
+impl Debug for Sealer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sealer").finish_non_exhaustive()
+    }
+}
+
+struct Opener(AeadCtxR<ChaCha20Poly1305, HkdfSha256, Kem>);
+
+impl HpkeOpener for Opener {
+    fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.0.open(ciphertext, aad).map_err(hpke_error)
+    }
+}
+
+impl Debug for Opener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Opener").finish_non_exhaustive()
+    }
+}
+
+fn hpke_error(err: impl Debug) -> rustls::Error {
+    rustls::Error::General(format!("HPKE error: {err:?}"))
+}
@@ -1,14 +1,29 @@
 use alloc::boxed::Box;
+#[cfg(feature = "mlkem")]
+use alloc::vec::Vec;
 
 use crypto::SupportedKxGroup;
 use rustls::crypto;
 use rustls::ffdhe_groups::FfdheGroup;
 
+#[cfg(feature = "x25519")]
 pub struct KeyExchange {
     priv_key: x25519_dalek::EphemeralSecret,
     pub_key: x25519_dalek::PublicKey,
 }
 
+#[cfg(feature = "x25519")]
+impl KeyExchange {
+    fn generate(rng: &mut impl rand_core::CryptoRngCore) -> Self {
+        let priv_key = x25519_dalek::EphemeralSecret::random_from_rng(rng);
+        Self {
+            pub_key: (&priv_key).into(),
+            priv_key,
+        }
+    }
+}
+
+#[cfg(feature = "x25519")]
 impl crypto::ActiveKeyExchange for KeyExchange {
     fn complete(
         self: Box<KeyExchange>,
@@ -19,6 +34,10 @@ impl crypto::ActiveKeyExchange for KeyExchange {
             .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
         let their_pub = x25519_dalek::PublicKey::from(peer_array);
         let shared_secret = self.priv_key.diffie_hellman(&their_pub);
+        // a low-order peer point gives an all-zero secret, which RFC8446 section 7.4.2 rejects
+        if !shared_secret.was_contributory() {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
         Ok(crypto::SharedSecret::from(&shared_secret.as_bytes()[..]))
     }
 
@@ -35,25 +54,548 @@ impl crypto::ActiveKeyExchange for KeyExchange {
     }
 }
 
-pub const ALL_KX_GROUPS: &[&dyn SupportedKxGroup] = &[&X25519 as &dyn SupportedKxGroup];
+pub const ALL_KX_GROUPS: &[&dyn SupportedKxGroup] = &[
+    #[cfg(feature = "mlkem")]
+    &X25519MlKem768,
+    #[cfg(feature = "x25519")]
+    &X25519,
+    #[cfg(feature = "secp256r1")]
+    &SecP256R1,
+    #[cfg(feature = "secp384r1")]
+    &SecP384R1,
+];
+
+/// The groups of [`crate::provider()`]: [`ALL_KX_GROUPS`] without the TLS1.3-only ones when
+/// the build also has TLS1.2 suites.
+pub const DEFAULT_KX_GROUPS: &[&dyn SupportedKxGroup] = &[
+    #[cfg(all(feature = "mlkem", not(feature = "tls12")))]
+    &X25519MlKem768,
+    #[cfg(feature = "x25519")]
+    &X25519,
+    #[cfg(feature = "secp256r1")]
+    &SecP256R1,
+    #[cfg(feature = "secp384r1")]
+    &SecP384R1,
+];
 
+#[cfg(feature = "x25519")]
 #[derive(Debug)]
 pub struct X25519;
 
+#[cfg(feature = "x25519")]
 impl crypto::SupportedKxGroup for X25519 {
     fn start(&self) -> Result<Box<dyn crypto::ActiveKeyExchange>, rustls::Error> {
-        let priv_key = x25519_dalek::EphemeralSecret::random_from_rng(rand_core::OsRng);
-        Ok(Box::new(KeyExchange {
-            pub_key: (&priv_key).into(),
+        Ok(Box::new(KeyExchange::generate(&mut rand_core::OsRng)))
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn name(&self) -> rustls::NamedGroup {
+        rustls::NamedGroup::X25519
+    }
+}
+
+#[cfg(feature = "secp256r1")]
+pub struct P256KeyExchange {
+    priv_key: p256::ecdh::EphemeralSecret,
+    pub_key: p256::EncodedPoint,
+}
+
+#[cfg(feature = "secp256r1")]
+impl P256KeyExchange {
+    fn generate(rng: &mut impl rand_core::CryptoRngCore) -> Self {
+        let priv_key = p256::ecdh::EphemeralSecret::random(rng);
+        Self {
+            pub_key: priv_key.public_key().into(),
+            priv_key,
+        }
+    }
+}
+
+#[cfg(feature = "secp256r1")]
+impl crypto::ActiveKeyExchange for P256KeyExchange {
+    fn complete(
+        self: Box<P256KeyExchange>,
+        peer: &[u8],
+    ) -> Result<crypto::SharedSecret, rustls::Error> {
+        // TLS only allows the uncompressed point format (RFC8446 4.2.8.2).
+        if peer.len() != 65 || peer[0] != UNCOMPRESSED_POINT {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
+        // `from_sec1_bytes` rejects the identity and points that are not on the curve.
+        let their_pub = p256::PublicKey::from_sec1_bytes(peer)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+        let shared_secret = self.priv_key.diffie_hellman(&their_pub);
+        Ok(crypto::SharedSecret::from(
+            &shared_secret.raw_secret_bytes()[..],
+        ))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        self.pub_key.as_bytes()
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn group(&self) -> rustls::NamedGroup {
+        SecP256R1.name()
+    }
+}
+
+#[cfg(feature = "secp256r1")]
+#[derive(Debug)]
+pub struct SecP256R1;
+
+#[cfg(feature = "secp256r1")]
+impl crypto::SupportedKxGroup for SecP256R1 {
+    fn start(&self) -> Result<Box<dyn crypto::ActiveKeyExchange>, rustls::Error> {
+        Ok(Box::new(P256KeyExchange::generate(&mut rand_core::OsRng)))
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn name(&self) -> rustls::NamedGroup {
+        rustls::NamedGroup::secp256r1
+    }
+}
+
+#[cfg(feature = "secp384r1")]
+pub struct P384KeyExchange {
+    priv_key: p384::ecdh::EphemeralSecret,
+    pub_key: p384::EncodedPoint,
+}
+
+#[cfg(feature = "secp384r1")]
+impl P384KeyExchange {
+    fn generate(rng: &mut impl rand_core::CryptoRngCore) -> Self {
+        let priv_key = p384::ecdh::EphemeralSecret::random(rng);
+        Self {
+            pub_key: priv_key.public_key().into(),
             priv_key,
+        }
+    }
+}
+
+#[cfg(feature = "secp384r1")]
+impl crypto::ActiveKeyExchange for P384KeyExchange {
+    fn complete(
+        self: Box<P384KeyExchange>,
+        peer: &[u8],
+    ) -> Result<crypto::SharedSecret, rustls::Error> {
+        if peer.len() != 97 || peer[0] != UNCOMPRESSED_POINT {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
+        let their_pub = p384::PublicKey::from_sec1_bytes(peer)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+        let shared_secret = self.priv_key.diffie_hellman(&their_pub);
+        Ok(crypto::SharedSecret::from(
+            &shared_secret.raw_secret_bytes()[..],
+        ))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        self.pub_key.as_bytes()
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn group(&self) -> rustls::NamedGroup {
+        SecP384R1.name()
+    }
+}
+
+#[cfg(feature = "secp384r1")]
+#[derive(Debug)]
+pub struct SecP384R1;
+
+#[cfg(feature = "secp384r1")]
+impl crypto::SupportedKxGroup for SecP384R1 {
+    fn start(&self) -> Result<Box<dyn crypto::ActiveKeyExchange>, rustls::Error> {
+        Ok(Box::new(P384KeyExchange::generate(&mut rand_core::OsRng)))
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn name(&self) -> rustls::NamedGroup {
+        rustls::NamedGroup::secp384r1
+    }
+}
+
+#[cfg(any(feature = "secp256r1", feature = "secp384r1"))]
+const UNCOMPRESSED_POINT: u8 = 0x04;
+
+/// The X25519MLKEM768 hybrid group from draft-kwiatkowski-tls-ecdhe-mlkem.
+///
+/// Key shares and the shared secret are the ML-KEM-768 part followed by the X25519 part.
+#[cfg(feature = "mlkem")]
+#[derive(Debug)]
+pub struct X25519MlKem768;
+
+#[cfg(feature = "mlkem")]
+const X25519MLKEM768: rustls::NamedGroup = rustls::NamedGroup::Unknown(0x11ec);
+
+/// Groups that rustls would also negotiate for TLS1.2, where they don't work: the
+/// `ServerKeyExchange` carries the server's share first, so a KEM has nothing to encapsulate.
+#[cfg(feature = "tls12")]
+pub(crate) const TLS13_ONLY_GROUPS: &[rustls::NamedGroup] = &[
+    #[cfg(feature = "mlkem")]
+    X25519MLKEM768,
+];
+
+#[cfg(feature = "mlkem")]
+const X25519_LEN: usize = 32;
+#[cfg(feature = "mlkem")]
+const MLKEM768_ENCAP_LEN: usize = 1184;
+#[cfg(feature = "mlkem")]
+const MLKEM768_CIPHERTEXT_LEN: usize = 1088;
+#[cfg(feature = "mlkem")]
+const MLKEM768_SECRET_LEN: usize = 32;
+
+#[cfg(feature = "mlkem")]
+impl crypto::SupportedKxGroup for X25519MlKem768 {
+    fn start(&self) -> Result<Box<dyn crypto::ActiveKeyExchange>, rustls::Error> {
+        use ml_kem::{EncodedSizeUser, KemCore};
+
+        let (mlkem_priv, mlkem_pub) = ml_kem::MlKem768::generate(&mut rand_core::OsRng);
+        let x25519_priv = x25519_dalek::EphemeralSecret::random_from_rng(rand_core::OsRng);
+        let x25519_pub = x25519_dalek::PublicKey::from(&x25519_priv);
+
+        let mut pub_key = Vec::with_capacity(MLKEM768_ENCAP_LEN + X25519_LEN);
+        pub_key.extend_from_slice(&mlkem_pub.as_bytes());
+        pub_key.extend_from_slice(x25519_pub.as_bytes());
+
+        Ok(Box::new(X25519MlKem768KeyExchange {
+            mlkem_priv,
+            x25519_priv,
+            pub_key,
         }))
     }
 
+    fn start_and_complete(
+        &self,
+        client_share: &[u8],
+    ) -> Result<crypto::CompletedKeyExchange, rustls::Error> {
+        use ml_kem::kem::Encapsulate;
+        use ml_kem::{EncodedSizeUser, KemCore};
+
+        if client_share.len() != MLKEM768_ENCAP_LEN + X25519_LEN {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
+        let (mlkem_share, x25519_share) = client_share.split_at(MLKEM768_ENCAP_LEN);
+
+        let mlkem_pub = <ml_kem::MlKem768 as KemCore>::EncapsulationKey::from_bytes(
+            &mlkem_share
+                .try_into()
+                .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?,
+        );
+        let (ciphertext, mlkem_secret) = mlkem_pub
+            .encapsulate(&mut rand_core::OsRng)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+
+        let x25519_kx = X25519.start()?;
+        let mut pub_key = Vec::with_capacity(MLKEM768_CIPHERTEXT_LEN + X25519_LEN);
+        pub_key.extend_from_slice(&ciphertext);
+        pub_key.extend_from_slice(x25519_kx.pub_key());
+        let x25519_secret = x25519_kx.complete(x25519_share)?;
+
+        let mut secret = [0u8; MLKEM768_SECRET_LEN + X25519_LEN];
+        secret[..MLKEM768_SECRET_LEN].copy_from_slice(&mlkem_secret);
+        secret[MLKEM768_SECRET_LEN..].copy_from_slice(x25519_secret.secret_bytes());
+
+        Ok(crypto::CompletedKeyExchange {
+            group: self.name(),
+            pub_key,
+            secret: crypto::SharedSecret::from(&secret[..]),
+        })
+    }
+
     fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
         None
     }
 
     fn name(&self) -> rustls::NamedGroup {
-        rustls::NamedGroup::X25519
+        X25519MLKEM768
+    }
+}
+
+#[cfg(feature = "mlkem")]
+pub struct X25519MlKem768KeyExchange {
+    mlkem_priv: <ml_kem::MlKem768 as ml_kem::KemCore>::DecapsulationKey,
+    x25519_priv: x25519_dalek::EphemeralSecret,
+    pub_key: Vec<u8>,
+}
+
+#[cfg(feature = "mlkem")]
+impl crypto::ActiveKeyExchange for X25519MlKem768KeyExchange {
+    fn complete(
+        self: Box<X25519MlKem768KeyExchange>,
+        peer: &[u8],
+    ) -> Result<crypto::SharedSecret, rustls::Error> {
+        use ml_kem::kem::Decapsulate;
+
+        if peer.len() != MLKEM768_CIPHERTEXT_LEN + X25519_LEN {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
+        let (ciphertext, x25519_share) = peer.split_at(MLKEM768_CIPHERTEXT_LEN);
+
+        let ciphertext = ml_kem::Ciphertext::<ml_kem::MlKem768>::try_from(ciphertext)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+        let mlkem_secret = self
+            .mlkem_priv
+            .decapsulate(&ciphertext)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+
+        let x25519_peer: [u8; X25519_LEN] = x25519_share
+            .try_into()
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+        let x25519_secret = self
+            .x25519_priv
+            .diffie_hellman(&x25519_dalek::PublicKey::from(x25519_peer));
+        if !x25519_secret.was_contributory() {
+            return Err(rustls::PeerMisbehaved::InvalidKeyShare.into());
+        }
+
+        let mut secret = [0u8; MLKEM768_SECRET_LEN + X25519_LEN];
+        secret[..MLKEM768_SECRET_LEN].copy_from_slice(&mlkem_secret);
+        secret[MLKEM768_SECRET_LEN..].copy_from_slice(x25519_secret.as_bytes());
+        Ok(crypto::SharedSecret::from(&secret[..]))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.pub_key
+    }
+
+    fn ffdhe_group(&self) -> Option<FfdheGroup<'static>> {
+        None
+    }
+
+    fn group(&self) -> rustls::NamedGroup {
+        X25519MLKEM768
+    }
+}
+
+#[cfg(all(
+    test,
+    any(feature = "x25519", feature = "secp256r1", feature = "secp384r1")
+))]
+mod tests {
+    use rustls::crypto::ActiveKeyExchange;
+    use wycheproof::TestResult;
+
+    use super::*;
+
+    /// Hands out a known private key in place of random bytes.
+    struct FixedRng(Vec<u8>);
+
+    impl rand_core::RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            assert!(dest.len() <= self.0.len(), "ran out of key bytes");
+            let rest = self.0.split_off(dest.len());
+            dest.copy_from_slice(&self.0);
+            self.0 = rest;
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl rand_core::CryptoRng for FixedRng {}
+
+    /// A big-endian scalar as exactly `len` bytes; Wycheproof keeps the DER sign byte.
+    #[cfg(any(feature = "secp256r1", feature = "secp384r1"))]
+    fn scalar(bytes: &[u8], len: usize) -> Vec<u8> {
+        let bytes = &bytes[bytes.len().saturating_sub(len)..];
+        let mut scalar = vec![0; len - bytes.len()];
+        scalar.extend_from_slice(bytes);
+        scalar
+    }
+
+    #[cfg(feature = "x25519")]
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[cfg(feature = "x25519")]
+    fn x25519(private_key: &[u8], peer: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        let kx = Box::new(KeyExchange::generate(&mut FixedRng(private_key.to_vec())));
+        kx.complete(peer)
+            .map(|secret| secret.secret_bytes().to_vec())
+    }
+
+    #[cfg(feature = "x25519")]
+    #[test]
+    fn x25519_rfc7748() {
+        // section 5.2
+        assert_eq!(
+            x25519(
+                &hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+                &hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c"),
+            )
+            .unwrap(),
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+        assert_eq!(
+            x25519(
+                &hex("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d"),
+                &hex("e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493"),
+            )
+            .unwrap(),
+            hex("95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957")
+        );
+
+        // section 6.1
+        let alice = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_kx = KeyExchange::generate(&mut FixedRng(alice.clone()));
+        let bob_kx = KeyExchange::generate(&mut FixedRng(bob.clone()));
+        assert_eq!(
+            alice_kx.pub_key(),
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob_kx.pub_key(),
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let shared = hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(x25519(&alice, bob_kx.pub_key()).unwrap(), shared);
+        assert_eq!(x25519(&bob, alice_kx.pub_key()).unwrap(), shared);
+    }
+
+    #[cfg(feature = "x25519")]
+    #[test]
+    fn x25519_rejects_low_order_points() {
+        let private_key = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        // the points of order 1, 2, 4 and 8, from https://cr.yp.to/ecdh.html#validate
+        for point in [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+            "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ] {
+            assert!(x25519(&private_key, &hex(point)).is_err(), "{point}");
+        }
+    }
+
+    #[cfg(feature = "x25519")]
+    #[test]
+    fn x25519_wycheproof() {
+        let set = wycheproof::xdh::TestSet::load(wycheproof::xdh::TestName::X25519).unwrap();
+        for test in set.test_groups.iter().flat_map(|group| &group.tests) {
+            let result = x25519(&test.private_key, &test.public_key);
+            // acceptable vectors are fine as long as they don't give an all-zero secret
+            if test.result == TestResult::Invalid || test.shared_secret.iter().all(|b| *b == 0) {
+                assert!(result.is_err(), "test {}: {}", test.tc_id, test.comment);
+            } else {
+                assert_eq!(
+                    result.unwrap()[..],
+                    test.shared_secret[..],
+                    "test {}: {}",
+                    test.tc_id,
+                    test.comment
+                );
+            }
+        }
+    }
+
+    /// Wycheproof's ECDH vectors with bare points, which is all TLS sends.
+    #[cfg(any(feature = "secp256r1", feature = "secp384r1"))]
+    fn ecdh_wycheproof(
+        name: wycheproof::ecdh::TestName,
+        key_exchange: impl Fn(&mut FixedRng) -> Box<dyn ActiveKeyExchange>,
+        scalar_len: usize,
+    ) {
+        let set = wycheproof::ecdh::TestSet::load(name).unwrap();
+        for test in set.test_groups.iter().flat_map(|group| &group.tests) {
+            let kx = key_exchange(&mut FixedRng(scalar(&test.private_key, scalar_len)));
+            let result = kx.complete(&test.public_key);
+            // the acceptable vectors are the compressed points
+            if test.result == TestResult::Valid {
+                assert_eq!(
+                    result.unwrap().secret_bytes(),
+                    &test.shared_secret[..],
+                    "test {}: {}",
+                    test.tc_id,
+                    test.comment
+                );
+            } else {
+                assert!(result.is_err(), "test {}: {}", test.tc_id, test.comment);
+            }
+        }
+    }
+
+    #[cfg(feature = "secp256r1")]
+    #[test]
+    fn p256_wycheproof() {
+        ecdh_wycheproof(
+            wycheproof::ecdh::TestName::EcdhSecp256r1Ecpoint,
+            |rng| Box::new(P256KeyExchange::generate(rng)),
+            32,
+        );
+    }
+
+    #[cfg(feature = "secp384r1")]
+    #[test]
+    fn p384_wycheproof() {
+        ecdh_wycheproof(
+            wycheproof::ecdh::TestName::EcdhSecp384r1Ecpoint,
+            |rng| Box::new(P384KeyExchange::generate(rng)),
+            48,
+        );
+    }
+
+    #[cfg(feature = "secp256r1")]
+    #[test]
+    fn p256_rejects_compressed_and_invalid_points() {
+        let kx = P256KeyExchange::generate(&mut rand_core::OsRng);
+        let mut point = kx.pub_key().to_vec();
+        // the same point, compressed
+        let mut compressed = vec![0x02 | (point[64] & 1)];
+        compressed.extend_from_slice(&point[1..33]);
+        // off the curve
+        point[64] ^= 1;
+        for peer in [&compressed[..], &point, &[0x00], &[]] {
+            let kx = Box::new(P256KeyExchange::generate(&mut rand_core::OsRng));
+            assert!(kx.complete(peer).is_err(), "{peer:02x?}");
+        }
+    }
+
+    #[cfg(feature = "secp384r1")]
+    #[test]
+    fn p384_rejects_compressed_and_invalid_points() {
+        let kx = P384KeyExchange::generate(&mut rand_core::OsRng);
+        let mut point = kx.pub_key().to_vec();
+        let mut compressed = vec![0x02 | (point[96] & 1)];
+        compressed.extend_from_slice(&point[1..49]);
+        point[96] ^= 1;
+        for peer in [&compressed[..], &point, &[0x00], &[]] {
+            let kx = Box::new(P384KeyExchange::generate(&mut rand_core::OsRng));
+            assert!(kx.complete(peer).is_err(), "{peer:02x?}");
+        }
     }
 }
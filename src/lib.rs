@@ -1,51 +1,103 @@
-#![no_std]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 extern crate alloc;
 
 use alloc::sync::Arc;
-use core::ops::Range;
-use defmt::{dbg, Debug2Format};
-use rustls::pki_types::UnixTime;
-
-use embassy_net::{
-    dns::DnsQueryType,
-    udp::{PacketMetadata, UdpSocket},
-    IpAddress, IpEndpoint, Ipv4Address, Stack,
-};
-use embassy_stm32::eth::{generic_smi::GenericSMI, Ethernet};
-use embassy_stm32::peripherals::ETH;
-use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, mutex::Mutex};
-use embassy_time::Instant;
+#[cfg(feature = "ecdsa-sign")]
+use pkcs8::AssociatedOid;
 use rustls::crypto::CryptoProvider;
 use rustls::crypto::KeyProvider;
 use rustls::crypto::SecureRandom;
 use rustls::pki_types::PrivateKeyDer;
-use rustls::time_provider::TimeProvider;
 
 mod aead;
+pub mod backend;
+mod builder;
+mod clock;
+pub mod dhcp;
+#[cfg(any(feature = "embassy", all(feature = "nts", feature = "std"), test))]
+mod exchange;
 mod hash;
 mod hmac;
+#[cfg(feature = "ech")]
+mod hpke;
 mod kx;
+#[cfg(feature = "embassy")]
+mod ntp;
+#[cfg(feature = "nts")]
+pub mod nts;
+#[cfg(feature = "roughtime")]
+pub mod roughtime;
+#[cfg(any(feature = "ecdsa-sign", feature = "ed25519-sign", feature = "rsa-sign"))]
 mod sign;
+pub mod sntp;
 mod verify;
 
-use core::time::Duration;
+pub use builder::{ProviderBuilder, SignatureSchemes};
+pub use clock::Clock;
+#[cfg(feature = "ech")]
+pub use hpke::HPKE_SUITES;
+#[cfg(feature = "embassy")]
+pub use ntp::{
+    get_time_from_ntp_server, init_call_to_ntp_server, set_dhcp_ntp_servers, time_provider,
+    Error as NtpError, NtpServer, NtpTime, CLOCK,
+};
+#[cfg(all(feature = "embassy", feature = "nts"))]
+pub use ntp::{get_time_from_nts_server, init_call_to_nts_server};
+#[cfg(all(feature = "embassy", feature = "roughtime"))]
+pub use ntp::{
+    get_time_from_roughtime_server, init_call_to_roughtime_server, RoughTime, RoughtimeServer,
+};
+
+#[cfg(not(any(
+    feature = "chacha",
+    feature = "aes-gcm",
+    feature = "ccm",
+    feature = "ccm-8"
+)))]
+compile_error!("enable at least one of the `chacha`, `aes-gcm`, `ccm` or `ccm-8` features");
 
-const TIME_BETWEEN_1900_1970: u64 = 2_208_988_800;
-const UNIX_TIME: u64 = 1705398728; // `date +%s`
+#[cfg(not(any(feature = "x25519", feature = "secp256r1", feature = "secp384r1")))]
+compile_error!("enable at least one of the `x25519`, `secp256r1` or `secp384r1` features");
 
-pub static NTP_TIME: Mutex<ThreadModeRawMutex, Option<u64>> = Mutex::new(None);
-pub static TIME_FROM_START: Mutex<ThreadModeRawMutex, Option<Instant>> = Mutex::new(None);
+#[cfg(not(any(
+    feature = "ecdsa-verify",
+    feature = "ed25519-verify",
+    feature = "rsa-verify"
+)))]
+compile_error!(
+    "enable at least one of the `ecdsa-verify`, `ed25519-verify` or `rsa-verify` features"
+);
 
+/// Every algorithm compiled into this build, in order of preference.
+///
+/// With the `tls12` feature, this leaves out `X25519MLKEM768`: rustls has no way to mark a
+/// group as TLS1.3-only, and a server would pick it for TLS1.2, where it can't work. Use
+/// [`tls13_provider()`] to offer it.
 pub fn provider() -> CryptoProvider {
     CryptoProvider {
         cipher_suites: ALL_CIPHER_SUITES.to_vec(),
-        kx_groups: kx::ALL_KX_GROUPS.to_vec(),
+        kx_groups: kx::DEFAULT_KX_GROUPS.to_vec(),
         signature_verification_algorithms: verify::ALGORITHMS,
         secure_random: &Provider,
         key_provider: &Provider,
     }
 }
+
+/// The TLS1.3 cipher suites of [`provider()`] with every key exchange group, including
+/// `X25519MLKEM768` with the `mlkem` feature.
+pub fn tls13_provider() -> CryptoProvider {
+    CryptoProvider {
+        cipher_suites: ALL_CIPHER_SUITES
+            .iter()
+            .copied()
+            .filter(|suite| suite.tls13().is_some())
+            .collect(),
+        kx_groups: kx::ALL_KX_GROUPS.to_vec(),
+        ..provider()
+    }
+}
+
 #[derive(Debug)]
 struct Provider;
 
@@ -63,63 +115,97 @@ impl KeyProvider for Provider {
         &self,
         key_der: PrivateKeyDer<'static>,
     ) -> Result<Arc<dyn rustls::sign::SigningKey>, rustls::Error> {
-        Ok(Arc::new(
-            sign::EcdsaSigningKeyP256::try_from(key_der).map_err(|err| {
-                let err = rustls::Error::General(alloc::format!("{}", err));
-                err
-            })?,
-        ))
-    }
-}
+        match &key_der {
+            #[cfg(any(feature = "ecdsa-sign", feature = "ed25519-sign", feature = "rsa-sign"))]
+            PrivateKeyDer::Pkcs8(der) => {
+                let info = pkcs8::PrivateKeyInfo::try_from(der.secret_pkcs8_der())
+                    .map_err(|err| rustls::Error::General(alloc::format!("{}", err)))?;
 
-#[derive(Debug)]
-struct StubTimeProvider;
-
-pub fn stub() -> Arc<dyn TimeProvider> {
-    Arc::new(StubTimeProvider)
-}
-
-impl TimeProvider for StubTimeProvider {
-    fn current_time(&self) -> Option<UnixTime> {
-        let ntp_time = embassy_futures::block_on(async {
-            let provisory = NTP_TIME.lock().await;
-            provisory.as_ref().map(|v| *v)
-        });
-
-        dbg!(ntp_time);
-
-        let time_from_start = embassy_futures::block_on(async { *TIME_FROM_START.lock().await });
-
-        dbg!(time_from_start);
-
-        let now_from_start = if let Some(now) = time_from_start {
-            now
-        } else {
-            unreachable!();
-        };
-
-        // Either the call to NTP server was successful and we can use NTP time ...
-        if let Some(now) = ntp_time {
-            let now_in_unix = now - TIME_BETWEEN_1900_1970;
-            dbg!(now_in_unix + now_from_start.elapsed().as_secs());
-            Some(UnixTime::since_unix_epoch(Duration::from_secs(
-                now_in_unix + now_from_start.elapsed().as_secs(),
-            )))
-        } else {
-            dbg!(Debug2Format(&UnixTime::since_unix_epoch(
-                Duration::from_secs(UNIX_TIME)
-            )));
-            // .. or we can use the hardcoded UNIX time
-            Some(UnixTime::since_unix_epoch(Duration::from_secs(UNIX_TIME)))
+                #[cfg(feature = "ed25519-sign")]
+                if info.algorithm.oid == ed25519_dalek::pkcs8::ALGORITHM_OID {
+                    return Ok(Arc::new(sign::Ed25519SigningKey::try_from(key_der)?));
+                }
+                #[cfg(feature = "rsa-sign")]
+                if info.algorithm.oid == rsa::pkcs1::ALGORITHM_OID {
+                    return Ok(Arc::new(sign::RsaSigningKey::try_from(key_der)?));
+                }
+                #[cfg(feature = "ecdsa-sign")]
+                if info.algorithm.oid == p256::elliptic_curve::ALGORITHM_OID {
+                    return if info.algorithm.parameters_oid().ok() == Some(p384::NistP384::OID) {
+                        Ok(Arc::new(sign::EcdsaSigningKeyP384::try_from(key_der)?))
+                    } else {
+                        Ok(Arc::new(sign::EcdsaSigningKeyP256::try_from(key_der)?))
+                    };
+                }
+                Err(rustls::Error::General(
+                    "unsupported private key algorithm".into(),
+                ))
+            }
+            // SEC1 keys don't have to name their curve, so try both
+            #[cfg(feature = "ecdsa-sign")]
+            PrivateKeyDer::Sec1(_) => {
+                match sign::EcdsaSigningKeyP256::try_from(key_der.clone_key()) {
+                    Ok(key) => Ok(Arc::new(key)),
+                    Err(_) => Ok(Arc::new(sign::EcdsaSigningKeyP384::try_from(key_der)?)),
+                }
+            }
+            #[cfg(feature = "rsa-sign")]
+            PrivateKeyDer::Pkcs1(_) => Ok(Arc::new(sign::RsaSigningKey::try_from(key_der)?)),
+            _ => Err(rustls::Error::General(
+                "unsupported private key format".into(),
+            )),
         }
     }
 }
 
 static ALL_CIPHER_SUITES: &[rustls::SupportedCipherSuite] = &[
+    #[cfg(feature = "chacha")]
     TLS13_CHACHA20_POLY1305_SHA256,
+    #[cfg(feature = "aes-gcm")]
+    TLS13_AES_128_GCM_SHA256,
+    #[cfg(feature = "aes-gcm")]
+    TLS13_AES_256_GCM_SHA384,
+    #[cfg(all(feature = "chacha", feature = "tls12"))]
+    TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+    #[cfg(all(feature = "chacha", feature = "tls12"))]
     TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    #[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+    TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    #[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+    TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    #[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+    TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    #[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+    TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+    // CCM suites come last, so they are only negotiated with peers that need them
+    #[cfg(feature = "ccm")]
+    TLS13_AES_128_CCM_SHA256,
+    #[cfg(all(feature = "ccm", feature = "tls12"))]
+    TLS_ECDHE_ECDSA_WITH_AES_128_CCM,
+    #[cfg(feature = "ccm-8")]
+    TLS13_AES_128_CCM_8_SHA256,
+    #[cfg(all(feature = "ccm-8", feature = "tls12"))]
+    TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8,
 ];
 
+#[cfg(feature = "tls12")]
+const TLS12_ECDSA_SCHEMES: &[rustls::SignatureScheme] = &[
+    rustls::SignatureScheme::ED25519,
+    rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
+    rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
+];
+
+#[cfg(all(feature = "tls12", any(feature = "chacha", feature = "aes-gcm")))]
+const TLS12_RSA_SCHEMES: &[rustls::SignatureScheme] = &[
+    rustls::SignatureScheme::RSA_PSS_SHA512,
+    rustls::SignatureScheme::RSA_PSS_SHA384,
+    rustls::SignatureScheme::RSA_PSS_SHA256,
+    rustls::SignatureScheme::RSA_PKCS1_SHA512,
+    rustls::SignatureScheme::RSA_PKCS1_SHA384,
+    rustls::SignatureScheme::RSA_PKCS1_SHA256,
+];
+
+#[cfg(feature = "chacha")]
 pub static TLS13_CHACHA20_POLY1305_SHA256: rustls::SupportedCipherSuite =
     rustls::SupportedCipherSuite::Tls13(&rustls::Tls13CipherSuite {
         common: rustls::crypto::CipherSuiteCommon {
@@ -128,10 +214,51 @@ pub static TLS13_CHACHA20_POLY1305_SHA256: rustls::SupportedCipherSuite =
             confidentiality_limit: u64::MAX,
         },
         hkdf_provider: &rustls::crypto::tls13::HkdfUsingHmac(&hmac::Sha256Hmac),
-        aead_alg: &aead::Chacha20Poly1305,
+        aead_alg: &aead::chacha::Chacha20Poly1305,
+        quic: Some(&aead::chacha::Chacha20Poly1305),
+    });
+
+#[cfg(feature = "aes-gcm")]
+pub static TLS13_AES_128_GCM_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls13(&rustls::Tls13CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS13_AES_128_GCM_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 24,
+        },
+        hkdf_provider: &rustls::crypto::tls13::HkdfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::gcm::Aes128Gcm,
         quic: None,
     });
 
+#[cfg(feature = "aes-gcm")]
+pub static TLS13_AES_256_GCM_SHA384: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls13(&rustls::Tls13CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS13_AES_256_GCM_SHA384,
+            hash_provider: &hash::Sha384,
+            confidentiality_limit: 1 << 24,
+        },
+        hkdf_provider: &rustls::crypto::tls13::HkdfUsingHmac(&hmac::Sha384Hmac),
+        aead_alg: &aead::gcm::Aes256Gcm,
+        quic: None,
+    });
+
+#[cfg(all(feature = "chacha", feature = "tls12"))]
+pub static TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: u64::MAX,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_ECDSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::chacha::Chacha20Poly1305,
+    });
+
+#[cfg(all(feature = "chacha", feature = "tls12"))]
 pub static TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256: rustls::SupportedCipherSuite =
     rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
         common: rustls::crypto::CipherSuiteCommon {
@@ -140,67 +267,117 @@ pub static TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256: rustls::SupportedCipherS
             confidentiality_limit: u64::MAX,
         },
         kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
-        sign: &[
-            rustls::SignatureScheme::RSA_PSS_SHA256,
-            rustls::SignatureScheme::RSA_PKCS1_SHA256,
-        ],
+        sign: TLS12_RSA_SCHEMES,
         prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
-        aead_alg: &aead::Chacha20Poly1305,
+        aead_alg: &aead::chacha::Chacha20Poly1305,
     });
-pub async fn init_call_to_ntp_server(stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>) {
-    // TODO: SPIN once
-    let ntp_time = get_time_from_ntp_server(stack).await;
-    NTP_TIME.lock().await.replace(ntp_time);
-}
 
-pub async fn get_time_from_ntp_server(
-    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
-) -> u64 {
-    const NTP_PACKET_SIZE: usize = 48;
-    const TX_SECONDS: Range<usize> = 40..44;
-
-    let ntp_server_addr = stack
-        .dns_query("time.cloudflare.com", DnsQueryType::A)
-        .await;
-
-    let ntp_sever = if let Ok(net_server_addr) = ntp_server_addr {
-        let adr = net_server_addr.first().unwrap().clone();
-        IpAddress::from(adr)
-    } else {
-        // Cloudflare server we know works!
-        IpAddress::from(Ipv4Address::new(162, 159, 200, 1))
-    };
-
-    let ntp_server = IpEndpoint {
-        addr: ntp_sever,
-        port: 123,
-    };
-    let mut rx_meta = [PacketMetadata::EMPTY; 16];
-    let mut rx_buffer = [0; 6400];
-    let mut tx_meta = [PacketMetadata::EMPTY; 16];
-    let mut tx_buffer = [0; 6400];
-    let mut buf = [0u8; NTP_PACKET_SIZE];
-
-    let mut sock = UdpSocket::new(
-        stack,
-        &mut rx_meta,
-        &mut rx_buffer,
-        &mut tx_meta,
-        &mut tx_buffer,
-    );
-
-    sock.bind(45698).unwrap();
-
-    // this magic number means
-    // - use NTPv3
-    // - we are a client
-    buf[0] = 0x1b;
-    sock.send_to(&buf, ntp_server).await.unwrap();
-
-    let mut response = buf;
-
-    let (_read, _ntc_peer) = sock.recv_from(&mut response).await.unwrap();
-
-    let transmit_seconds = u32::from_be_bytes(response[TX_SECONDS].try_into().unwrap());
-    transmit_seconds.into()
-}
+#[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+pub static TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 24,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_ECDSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::gcm::Aes128Gcm,
+    });
+
+#[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+pub static TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 24,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_RSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::gcm::Aes128Gcm,
+    });
+
+#[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+pub static TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            hash_provider: &hash::Sha384,
+            confidentiality_limit: 1 << 24,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_ECDSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha384Hmac),
+        aead_alg: &aead::gcm::Aes256Gcm,
+    });
+
+#[cfg(all(feature = "aes-gcm", feature = "tls12"))]
+pub static TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            hash_provider: &hash::Sha384,
+            confidentiality_limit: 1 << 24,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_RSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha384Hmac),
+        aead_alg: &aead::gcm::Aes256Gcm,
+    });
+
+#[cfg(feature = "ccm")]
+pub static TLS13_AES_128_CCM_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls13(&rustls::Tls13CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS13_AES_128_CCM_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 23,
+        },
+        hkdf_provider: &rustls::crypto::tls13::HkdfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::ccm::Aes128Ccm,
+        quic: None,
+    });
+
+#[cfg(feature = "ccm-8")]
+pub static TLS13_AES_128_CCM_8_SHA256: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls13(&rustls::Tls13CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS13_AES_128_CCM_8_SHA256,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 23,
+        },
+        hkdf_provider: &rustls::crypto::tls13::HkdfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::ccm::Aes128Ccm8,
+        quic: None,
+    });
+
+#[cfg(all(feature = "ccm", feature = "tls12"))]
+pub static TLS_ECDHE_ECDSA_WITH_AES_128_CCM: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CCM,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 23,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_ECDSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::ccm::Aes128Ccm,
+    });
+
+#[cfg(all(feature = "ccm-8", feature = "tls12"))]
+pub static TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8: rustls::SupportedCipherSuite =
+    rustls::SupportedCipherSuite::Tls12(&rustls::Tls12CipherSuite {
+        common: rustls::crypto::CipherSuiteCommon {
+            suite: rustls::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8,
+            hash_provider: &hash::Sha256,
+            confidentiality_limit: 1 << 23,
+        },
+        kx: rustls::crypto::KeyExchangeAlgorithm::ECDHE,
+        sign: TLS12_ECDSA_SCHEMES,
+        prf_provider: &rustls::crypto::tls12::PrfUsingHmac(&hmac::Sha256Hmac),
+        aead_alg: &aead::ccm::Aes128Ccm8,
+    });
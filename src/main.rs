@@ -23,20 +23,27 @@ use embassy_stm32::rng::Rng;
 use embassy_stm32::time::Hertz;
 use embassy_stm32::{bind_interrupts, eth, peripherals, rng, Config};
 
+use embassy_time::Duration;
 use embassy_time::Timer;
-use embassy_time::{Duration, Instant};
 use embedded_io_async::Write;
 use no_std_embedded_demo as lib;
 use rustls::client::{ClientConnectionData, EarlyDataError, UnbufferedClientConnection};
+#[cfg(feature = "ech")]
+use rustls::client::{EchConfig, EchMode};
+#[cfg(feature = "ech")]
+use rustls::pki_types::EchConfigListBytes;
 use rustls::pki_types::{DnsName, InvalidDnsNameError, ServerName};
 
-use crate::lib::{init_call_to_ntp_server, TIME_FROM_START};
+use crate::lib::{init_call_to_ntp_server, NtpServer};
 use rustls::unbuffered::{
     AppDataRecord, ConnectionState, EncodeError, EncryptError, InsufficientSizeError,
     UnbufferedStatus, WriteTraffic,
 };
+#[cfg(feature = "tls12")]
 #[allow(unused_imports)]
-use rustls::version::{TLS12, TLS13};
+use rustls::version::TLS12;
+#[allow(unused_imports)]
+use rustls::version::TLS13;
 use rustls::{ClientConfig, RootCertStore};
 
 use static_cell::StaticCell;
@@ -53,7 +60,11 @@ bind_interrupts!(struct Irqs {
 const KB: usize = 1024;
 // Note that some sites like www.google.com/www.cloudflare.com need
 // extra heap allocation here, this is the reason for
+#[cfg(not(feature = "mlkem"))]
 const HEAP_SIZE: usize = 25 * KB / 4;
+// the ML-KEM-768 decapsulation key and key share live on the heap during the handshake
+#[cfg(feature = "mlkem")]
+const HEAP_SIZE: usize = 30 * KB / 4;
 
 const INCOMING_TLS_BUFSIZ: usize = 6 * KB;
 const MAC_ADDR: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
@@ -62,14 +73,39 @@ const MAX_ITERATIONS: usize = 300;
 const SEND_EARLY_DATA: bool = false;
 const EARLY_DATA: &[u8] = b"hello";
 
-const OUTGOING_TLS_BUFSIZ: usize = KB / 2;
+#[cfg(not(feature = "mlkem"))]
+const CLIENT_HELLO_BUFSIZ: usize = KB / 2;
+// the ClientHello carries the 1216-byte X25519MLKEM768 key share
+#[cfg(feature = "mlkem")]
+const CLIENT_HELLO_BUFSIZ: usize = 2 * KB;
+// with ECH the outer ClientHello also carries the encrypted inner one
+const OUTGOING_TLS_BUFSIZ: usize = if cfg!(feature = "ech") {
+    2 * CLIENT_HELLO_BUFSIZ
+} else {
+    CLIENT_HELLO_BUFSIZ
+};
 const TCP_RX_BUFSIZ: usize = KB;
 const TCP_TX_BUFSIZ: usize = KB / 2;
 
 const SERVER_NAME: &str = "www.rust-lang.org";
 
+// ECHConfigList of `SERVER_NAME`: the base64-decoded `ech` parameter of its HTTPS DNS record,
+// e.g. from `dig +short HTTPS crypto.cloudflare.com` (see the README).
+// Leave it empty to connect without Encrypted Client Hello.
+#[cfg(feature = "ech")]
+const ECH_CONFIG_LIST: &[u8] = &[];
+
 const SERVER_PORT: u16 = 443;
 
+// In order of preference. `NtpServer::Dhcp` stays empty unless the DHCPACK of the lease is
+// given to `lib::set_dhcp_ntp_servers`, which embassy-net's DHCP client can't do.
+const NTP_SERVERS: &[NtpServer] = &[
+    NtpServer::Dhcp,
+    NtpServer::Host("time.cloudflare.com"),
+    // Cloudflare server we know works!
+    NtpServer::Address(IpAddress::Ipv4(Ipv4Address::new(162, 159, 200, 1))),
+];
+
 #[embassy_executor::main]
 async fn start(spawner: Spawner) -> ! {
     heap::init();
@@ -95,9 +131,15 @@ async fn main(
 ) -> Result<()> {
     let stack = set_up_network_stack(spawner).await?;
 
-    init_call_to_ntp_server(stack).await;
-
-    TIME_FROM_START.lock().await.replace(Instant::now());
+    let ntp = init_call_to_ntp_server(stack, NTP_SERVERS).await?;
+    info!(
+        "NTP time {}s from {} at {} (stratum {}, round trip {}ms)",
+        ntp.sample.unix.as_secs(),
+        Debug2Format(&ntp.server),
+        ntp.address,
+        ntp.sample.stratum,
+        ntp.sample.delay.as_millis()
+    );
 
     info!("querying host {:?}...", SERVER_NAME);
     let dns_results = stack.dns_query(SERVER_NAME, DnsQueryType::A).await?;
@@ -116,14 +158,27 @@ async fn main(
     let mut root_store = RootCertStore::empty();
     root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
 
-    let time_provider = lib::stub();
-    let mut tls_config = ClientConfig::builder_with_details(lib::provider().into(), time_provider)
-        .with_safe_default_protocol_versions()
-        .unwrap()
+    let time_provider = lib::time_provider();
+    // `provider()` leaves X25519MLKEM768 out when TLS1.2 is enabled
+    #[cfg(feature = "mlkem")]
+    let provider = lib::tls13_provider();
+    #[cfg(not(feature = "mlkem"))]
+    let provider = lib::provider();
+    let tls_config = ClientConfig::builder_with_details(provider.into(), time_provider);
+    #[cfg(feature = "ech")]
+    let tls_config = if ECH_CONFIG_LIST.is_empty() {
+        tls_config.with_safe_default_protocol_versions().unwrap()
+    } else {
+        let ech_config =
+            EchConfig::new(EchConfigListBytes::from(ECH_CONFIG_LIST), lib::HPKE_SUITES)?;
+        tls_config.with_ech(EchMode::Enable(ech_config))?
+    };
+    #[cfg(not(feature = "ech"))]
+    let tls_config = tls_config.with_safe_default_protocol_versions().unwrap();
+    let mut tls_config = tls_config
         .with_root_certificates(root_store)
         .with_no_client_auth();
     tls_config.enable_early_data = SEND_EARLY_DATA;
-    //tls_config.time_provider = lib::stub();
 
     let tls_config = Arc::new(tls_config);
 
@@ -529,6 +584,7 @@ enum Error {
     DnsError(dns::Error),
     NoDnsResolution,
     EarlyDataError(EarlyDataError),
+    Ntp(lib::NtpError),
 }
 
 impl From<EncodeError> for Error {
@@ -590,6 +646,12 @@ impl From<EarlyDataError> for Error {
     }
 }
 
+impl From<lib::NtpError> for Error {
+    fn from(v: lib::NtpError) -> Self {
+        Self::Ntp(v)
+    }
+}
+
 mod getrandom {
     use embassy_stm32::peripherals::RNG;
     use embassy_stm32::rng::Rng;
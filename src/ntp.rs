@@ -0,0 +1,460 @@
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use rand_core::{OsRng, RngCore};
+use rustls::pki_types::UnixTime;
+
+use embassy_net::{
+    dns::{self, DnsQueryType},
+    udp::{BindError, PacketMetadata, RecvError, SendError, UdpSocket},
+    IpAddress, IpEndpoint, Ipv4Address, Stack,
+};
+use embassy_stm32::eth::{generic_smi::GenericSMI, Ethernet};
+use embassy_stm32::peripherals::ETH;
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use embassy_time::{with_timeout, Instant};
+use rustls::time_provider::TimeProvider;
+
+use crate::clock::Clock;
+use crate::dhcp;
+use crate::exchange::{self, Transport};
+#[cfg(feature = "nts")]
+use crate::nts::{self, NtsSession};
+#[cfg(feature = "roughtime")]
+use crate::roughtime;
+use crate::sntp::{self, Sample};
+
+/// Requests per server address, each waiting [`TIMEOUT`] for a reply.
+pub const ATTEMPTS: usize = 3;
+pub const TIMEOUT: embassy_time::Duration = embassy_time::Duration::from_secs(2);
+
+/// The wall clock behind [`time_provider()`], set by [`init_call_to_ntp_server`] (or
+/// `init_call_to_nts_server`, `init_call_to_roughtime_server`).
+pub static CLOCK: Clock = Clock::new();
+
+#[derive(Debug)]
+struct NtpTimeProvider;
+
+/// Reads [`CLOCK`]: `None` until a time server answered, then its time plus the time
+/// elapsed since, without blocking.
+pub fn time_provider() -> Arc<dyn TimeProvider> {
+    Arc::new(NtpTimeProvider)
+}
+
+impl TimeProvider for NtpTimeProvider {
+    fn current_time(&self) -> Option<UnixTime> {
+        CLOCK.now(Instant::now().as_micros())
+    }
+}
+
+/// Where to ask for the time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NtpServer {
+    /// Resolved to its IPv4 and then its IPv6 addresses with the DNS servers of the stack.
+    Host(&'static str),
+    Address(IpAddress),
+    /// The servers of DHCP option 42 given to [`set_dhcp_ntp_servers`]; none until then.
+    Dhcp,
+}
+
+/// The addresses behind [`NtpServer::Dhcp`].
+static DHCP_NTP_SERVERS: Mutex<CriticalSectionRawMutex, RefCell<Vec<Ipv4Address>>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+/// Keeps the NTP servers of `dhcp_ack`, the DHCPACK of the lease, for [`NtpServer::Dhcp`],
+/// and returns how many there are.
+///
+/// embassy-net doesn't hand out the DHCPACK, see [`crate::dhcp`].
+pub fn set_dhcp_ntp_servers(dhcp_ack: &[u8]) -> Result<usize, dhcp::Error> {
+    let servers: Vec<Ipv4Address> = dhcp::ntp_servers(dhcp_ack)?
+        .iter()
+        .map(|address| {
+            let [a, b, c, d] = address.octets();
+            Ipv4Address::new(a, b, c, d)
+        })
+        .collect();
+    let count = servers.len();
+    DHCP_NTP_SERVERS.lock(|kept| *kept.borrow_mut() = servers);
+    Ok(count)
+}
+
+/// The time from [`get_time_from_ntp_server`] (or `get_time_from_nts_server`), and where it
+/// came from.
+#[derive(Clone, Copy, Debug)]
+pub struct NtpTime {
+    pub sample: Sample,
+    pub server: NtpServer,
+    pub address: IpAddress,
+}
+
+/// Sets [`CLOCK`] from [`get_time_from_ntp_server`].
+pub async fn init_call_to_ntp_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    servers: &[NtpServer],
+) -> Result<NtpTime, Error> {
+    let time = get_time_from_ntp_server(stack, servers).await?;
+    CLOCK.set(time.sample.unix, time.sample.delay / 2, time.sample.at);
+    Ok(time)
+}
+
+/// Asks `servers` for the time with SNTPv4, in order, and returns the first valid reply.
+/// Each address of a server gets up to [`ATTEMPTS`] requests.
+///
+/// When they all fail, the error is the one of the last address tried.
+pub async fn get_time_from_ntp_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    servers: &[NtpServer],
+) -> Result<NtpTime, Error> {
+    let mut rx_meta = [PacketMetadata::EMPTY; 4];
+    let mut rx_buffer = [0; 4 * sntp::PACKET_LEN];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; sntp::PACKET_LEN];
+
+    let mut sock = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+
+    // an ephemeral port, harder to guess for off-path attackers than a fixed one
+    sock.bind(0).map_err(Error::Bind)?;
+
+    let mut error = Error::NoServer;
+    for &server in servers {
+        let addresses = match resolve(stack, server).await {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                error = Error::Dns(e);
+                continue;
+            }
+        };
+
+        for address in addresses {
+            match ask(&sock, IpEndpoint::new(address, sntp::PORT)).await {
+                Ok(sample) => {
+                    return Ok(NtpTime {
+                        sample,
+                        server,
+                        address,
+                    })
+                }
+                Err(e) => error = e,
+            }
+        }
+    }
+    Err(error)
+}
+
+/// Sets [`CLOCK`] from [`get_time_from_nts_server`].
+#[cfg(feature = "nts")]
+pub async fn init_call_to_nts_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    session: &mut NtsSession,
+) -> Result<NtpTime, Error> {
+    let time = get_time_from_nts_server(stack, session).await?;
+    CLOCK.set(time.sample.unix, time.sample.delay / 2, time.sample.at);
+    Ok(time)
+}
+
+/// Asks the NTPv4 server of `session` for the time with NTS, trying its addresses like
+/// [`get_time_from_ntp_server`], each up to [`nts::ATTEMPTS`] times with a new cookie.
+///
+/// NTS-KE can't run on the board (see [`crate::nts`]), so `session` comes from
+/// [`NtsSession::new`] with the response and the keys of a key exchange run elsewhere.
+#[cfg(feature = "nts")]
+pub async fn get_time_from_nts_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    session: &mut NtsSession,
+) -> Result<NtpTime, Error> {
+    // a request with its placeholders and a reply with its cookies are about 1 KB each
+    let mut rx_meta = [PacketMetadata::EMPTY; 2];
+    let mut rx_buffer = [0; 2 * nts::PACKET_LEN];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; nts::PACKET_LEN];
+
+    let mut sock = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    sock.bind(0).map_err(Error::Bind)?;
+
+    let (host, port) = session.ntp_server();
+    let addresses = resolve_host(stack, host).await.map_err(Error::Dns)?;
+    let mut error = Error::NoServer;
+    for address in addresses {
+        match ask_nts(&sock, IpEndpoint::new(address, port), session).await {
+            Ok(sample) => {
+                return Ok(NtpTime {
+                    sample,
+                    server: NtpServer::Address(address),
+                    address,
+                })
+            }
+            Err(e @ Error::Nts(nts::Error::NoCookie)) => return Err(e),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// A Roughtime server and its long-term Ed25519 public key, which authenticates its replies.
+#[cfg(feature = "roughtime")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoughtimeServer {
+    pub server: NtpServer,
+    pub public_key: [u8; 32],
+}
+
+/// The time from [`get_time_from_roughtime_server`].
+#[cfg(feature = "roughtime")]
+#[derive(Clone, Copy, Debug)]
+pub struct RoughTime {
+    /// The interval that the servers that answered agree on.
+    pub sample: roughtime::Sample,
+    /// How many servers answered.
+    pub servers: usize,
+}
+
+/// Sets [`CLOCK`] and its radius from [`get_time_from_roughtime_server`].
+#[cfg(feature = "roughtime")]
+pub async fn init_call_to_roughtime_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    servers: &[RoughtimeServer],
+) -> Result<RoughTime, Error> {
+    let time = get_time_from_roughtime_server(stack, servers).await?;
+    CLOCK.set(time.sample.unix, time.sample.radius, time.sample.at);
+    Ok(time)
+}
+
+/// Asks every one of `servers` for the time with Roughtime, trying the addresses of each
+/// like [`get_time_from_ntp_server`], and combines the verified replies with
+/// [`roughtime::combine`].
+///
+/// Servers that don't answer are skipped. When none does, the error is the one of the
+/// last address tried; [`Error::Inconsistent`] means two servers disagree.
+#[cfg(feature = "roughtime")]
+pub async fn get_time_from_roughtime_server(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    servers: &[RoughtimeServer],
+) -> Result<RoughTime, Error> {
+    let mut rx_meta = [PacketMetadata::EMPTY; 2];
+    let mut rx_buffer = [0; 2 * roughtime::REQUEST_LEN];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; roughtime::REQUEST_LEN];
+
+    let mut sock = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    sock.bind(0).map_err(Error::Bind)?;
+
+    let mut samples = Vec::new();
+    let mut error = Error::NoServer;
+    for server in servers {
+        let addresses = match resolve(stack, server.server).await {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                error = Error::Dns(e);
+                continue;
+            }
+        };
+
+        for address in addresses {
+            let endpoint = IpEndpoint::new(address, roughtime::PORT);
+            match ask_roughtime(&sock, endpoint, &server.public_key).await {
+                Ok(sample) => {
+                    samples.push(sample);
+                    break;
+                }
+                Err(e) => error = e,
+            }
+        }
+    }
+    if samples.is_empty() {
+        return Err(error);
+    }
+
+    Ok(RoughTime {
+        sample: roughtime::combine(&samples).ok_or(Error::Inconsistent)?,
+        servers: samples.len(),
+    })
+}
+
+async fn resolve(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    server: NtpServer,
+) -> Result<Vec<IpAddress>, dns::Error> {
+    match server {
+        NtpServer::Host(host) => resolve_host(stack, host).await,
+        NtpServer::Address(address) => Ok(vec![address]),
+        NtpServer::Dhcp => {
+            let servers = DHCP_NTP_SERVERS.lock(|kept| kept.borrow().clone());
+            Ok(servers.into_iter().map(IpAddress::Ipv4).collect())
+        }
+    }
+}
+
+/// The IPv4 and then the IPv6 addresses of `host`, which may be an address itself.
+async fn resolve_host(
+    stack: &'static Stack<Ethernet<'static, ETH, GenericSMI>>,
+    host: &str,
+) -> Result<Vec<IpAddress>, dns::Error> {
+    let mut addresses = Vec::new();
+    let mut error = None;
+    for query_type in [DnsQueryType::A, DnsQueryType::Aaaa] {
+        match stack.dns_query(host, query_type).await {
+            Ok(found) => addresses.extend(found),
+            Err(e) => error = Some(e),
+        }
+    }
+    match error {
+        Some(e) if addresses.is_empty() => Err(e),
+        _ => Ok(addresses),
+    }
+}
+
+async fn ask(sock: &UdpSocket<'_>, server: IpEndpoint) -> Result<Sample, Error> {
+    let mut error = Error::Timeout;
+    for _ in 0..ATTEMPTS {
+        match query(sock, server).await {
+            Ok(sample) => return Ok(sample),
+            // RFC5905 section 7.4: stop asking a server that sent a Kiss-o'-Death
+            Err(e @ Error::Sntp(sntp::Error::KissOfDeath(_))) => return Err(e),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+async fn query(sock: &UdpSocket<'_>, server: IpEndpoint) -> Result<Sample, Error> {
+    let mut nonce = [0; 8];
+    OsRng
+        .try_fill_bytes(&mut nonce)
+        .map_err(|_| Error::Random)?;
+    exchange::query(&Server(sock, server), nonce, TIMEOUT.into()).await
+}
+
+#[cfg(feature = "roughtime")]
+async fn ask_roughtime(
+    sock: &UdpSocket<'_>,
+    server: IpEndpoint,
+    public_key: &[u8; 32],
+) -> Result<roughtime::Sample, Error> {
+    let mut error = Error::Timeout;
+    for _ in 0..ATTEMPTS {
+        match query_roughtime(sock, server, public_key).await {
+            Ok(sample) => return Ok(sample),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+#[cfg(feature = "roughtime")]
+async fn query_roughtime(
+    sock: &UdpSocket<'_>,
+    server: IpEndpoint,
+    public_key: &[u8; 32],
+) -> Result<roughtime::Sample, Error> {
+    let mut nonce = [0; roughtime::NONCE_LEN];
+    OsRng
+        .try_fill_bytes(&mut nonce)
+        .map_err(|_| Error::Random)?;
+    exchange::query_roughtime(&Server(sock, server), public_key, nonce, TIMEOUT.into()).await
+}
+
+#[cfg(feature = "nts")]
+async fn ask_nts(
+    sock: &UdpSocket<'_>,
+    server: IpEndpoint,
+    session: &mut NtsSession,
+) -> Result<Sample, Error> {
+    let mut error = Error::Timeout;
+    for _ in 0..nts::ATTEMPTS {
+        match exchange::query_nts(&Server(sock, server), session, nts::TIMEOUT).await {
+            Ok(sample) => return Ok(sample),
+            // out of cookies, or told to stop asking
+            Err(
+                e
+                @ Error::Nts(nts::Error::NoCookie | nts::Error::Sntp(sntp::Error::KissOfDeath(_))),
+            ) => return Err(e),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// The socket of [`get_time_from_ntp_server`] (or `get_time_from_nts_server`,
+/// `get_time_from_roughtime_server`), sending to one server.
+struct Server<'a, 'b>(&'a UdpSocket<'b>, IpEndpoint);
+
+impl Transport for Server<'_, '_> {
+    type Error = Error;
+
+    fn now(&self) -> u64 {
+        Instant::now().as_micros()
+    }
+
+    async fn send(&self, packet: &[u8]) -> Result<(), Error> {
+        self.0.send_to(packet, self.1).await.map_err(Error::Send)
+    }
+
+    async fn recv(&self, buffer: &mut [u8], deadline: u64) -> Result<usize, Error> {
+        let remaining = Instant::from_micros(deadline).saturating_duration_since(Instant::now());
+        let (len, _) = with_timeout(remaining, self.0.recv_from(buffer))
+            .await
+            .map_err(|_| Error::Timeout)?
+            .map_err(Error::Recv)?;
+        Ok(len)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The list of servers is empty.
+    NoServer,
+    Dns(dns::Error),
+    Bind(BindError),
+    Send(SendError),
+    Recv(RecvError),
+    Random,
+    /// No valid reply within [`TIMEOUT`].
+    Timeout,
+    Sntp(sntp::Error),
+    #[cfg(feature = "nts")]
+    Nts(nts::Error),
+    #[cfg(feature = "roughtime")]
+    Roughtime(roughtime::Error),
+    /// The verified replies of two Roughtime servers don't overlap.
+    #[cfg(feature = "roughtime")]
+    Inconsistent,
+}
+
+impl From<sntp::Error> for Error {
+    fn from(e: sntp::Error) -> Self {
+        Self::Sntp(e)
+    }
+}
+
+#[cfg(feature = "nts")]
+impl From<nts::Error> for Error {
+    fn from(e: nts::Error) -> Self {
+        Self::Nts(e)
+    }
+}
+
+#[cfg(feature = "roughtime")]
+impl From<roughtime::Error> for Error {
+    fn from(e: roughtime::Error) -> Self {
+        Self::Roughtime(e)
+    }
+}
@@ -0,0 +1,706 @@
+//! A Network Time Security client (RFC8915): NTS-KE over TLS1.3 with [`crate::provider()`],
+//! then NTPv4 requests authenticated with AEAD_AES_SIV_CMAC_256 and the cookies of the server.
+//!
+//! The protocol is apart from the I/O, like [`crate::sntp`]: [`ke_request`] and
+//! [`KeResponse`] are the NTS-KE messages inside the TLS stream, [`Keys`] come from its
+//! exporter, and [`NtsSession`] builds and checks the NTP packets. With `std`,
+//! [`NtsSession::key_exchange`] and [`NtsSession::query`] run them over `std::net` sockets;
+//! with `embassy`, `init_call_to_nts_server` sends the NTP packets over embassy-net.
+//!
+//! NTS-KE doesn't run on the board yet: the keys come from the TLS exporter, which rustls
+//! 0.23 only offers on the `std` `ClientConnection`, not on `UnbufferedClientConnection`.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use aes_siv::aead::{Aead, Payload};
+use aes_siv::{Aes128SivAead, KeyInit, Nonce};
+use rand_core::{OsRng, RngCore};
+
+use crate::sntp::{self, Request, Sample};
+
+pub const KE_PORT: u16 = 4460;
+/// How long to wait for the NTS-KE server and for each NTP reply.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+/// NTP requests per query, each with a new cookie.
+pub const ATTEMPTS: usize = 3;
+/// The largest NTP packet sent or received, with the extension fields.
+pub const PACKET_LEN: usize = 1280;
+pub const KEY_LEN: usize = 32;
+/// The ALPN protocol of NTS-KE, for `ClientConfig::alpn_protocols`.
+pub const ALPN: &[u8] = b"ntske/1";
+/// The cookies to keep at hand (RFC8915 section 5.7).
+const MAX_COOKIES: usize = 8;
+
+const EXPORTER_LABEL: &[u8] = b"EXPORTER-network-time-security";
+const NTPV4: u16 = 0;
+const AEAD_AES_SIV_CMAC_256: u16 = 15;
+const NONCE_LEN: usize = 16;
+const UNIQUE_ID_LEN: usize = 32;
+
+// NTS-KE record types (RFC8915 section 4)
+const CRITICAL: u16 = 0x8000;
+const END_OF_MESSAGE: u16 = 0;
+const NEXT_PROTOCOL: u16 = 1;
+const KE_ERROR: u16 = 2;
+const KE_WARNING: u16 = 3;
+const AEAD_ALGORITHM: u16 = 4;
+const NEW_COOKIE: u16 = 5;
+const SERVER: u16 = 6;
+const PORT: u16 = 7;
+
+// NTP extension field types (RFC8915 section 5)
+const UNIQUE_ID: u16 = 0x0104;
+const COOKIE: u16 = 0x0204;
+const COOKIE_PLACEHOLDER: u16 = 0x0304;
+const AUTHENTICATOR: u16 = 0x0404;
+
+/// The NTS-KE request for NTPv4 with AEAD_AES_SIV_CMAC_256, to send over TLS1.3 with the
+/// [`ALPN`] protocol.
+pub fn ke_request() -> Vec<u8> {
+    let mut request = Vec::new();
+    record(&mut request, CRITICAL | NEXT_PROTOCOL, &NTPV4.to_be_bytes());
+    record(
+        &mut request,
+        CRITICAL | AEAD_ALGORITHM,
+        &AEAD_AES_SIV_CMAC_256.to_be_bytes(),
+    );
+    record(&mut request, CRITICAL | END_OF_MESSAGE, &[]);
+    request
+}
+
+/// What an NTS-KE server answered to [`ke_request`].
+#[derive(Debug, Default)]
+pub struct KeResponse {
+    /// The NTPv4 server, when it isn't the NTS-KE server itself.
+    pub ntp_server: Option<String>,
+    pub ntp_port: Option<u16>,
+    pub cookies: Vec<Vec<u8>>,
+}
+
+impl KeResponse {
+    /// Parses the records that the server sent so far: `None` until its End of Message.
+    pub fn parse(mut received: &[u8]) -> Result<Option<Self>, Error> {
+        let mut response = Self::default();
+        let (mut protocol, mut aead) = (false, false);
+        loop {
+            let [t0, t1, l0, l1, rest @ ..] = received else {
+                return Ok(None);
+            };
+            let typ = u16::from_be_bytes([*t0, *t1]);
+            let len = usize::from(u16::from_be_bytes([*l0, *l1]));
+            let Some((body, rest)) = rest.split_at_checked(len) else {
+                return Ok(None);
+            };
+            received = rest;
+
+            match typ & !CRITICAL {
+                END_OF_MESSAGE => break,
+                NEXT_PROTOCOL => protocol = body == NTPV4.to_be_bytes(),
+                KE_ERROR => return Err(Error::KeServer(u16_body(body)?)),
+                AEAD_ALGORITHM => aead = body == AEAD_AES_SIV_CMAC_256.to_be_bytes(),
+                NEW_COOKIE if response.cookies.len() < MAX_COOKIES => {
+                    response.cookies.push(body.to_vec())
+                }
+                NEW_COOKIE | KE_WARNING => {}
+                SERVER => {
+                    let server = core::str::from_utf8(body)
+                        .map_err(|_| Error::KeProtocol("invalid NTPv4 server"))?;
+                    response.ntp_server = Some(server.to_string());
+                }
+                PORT => response.ntp_port = Some(u16_body(body)?),
+                _ if typ & CRITICAL != 0 => {
+                    return Err(Error::KeProtocol("unknown critical record"))
+                }
+                _ => {}
+            }
+        }
+        if !protocol {
+            return Err(Error::KeProtocol("NTPv4 wasn't selected"));
+        }
+        if !aead {
+            return Err(Error::KeProtocol("AEAD_AES_SIV_CMAC_256 wasn't selected"));
+        }
+        if response.cookies.is_empty() {
+            return Err(Error::NoCookie);
+        }
+        Ok(Some(response))
+    }
+}
+
+/// The keys of the NTP requests and of the replies, exported from the NTS-KE connection.
+#[derive(Debug)]
+pub struct Keys {
+    pub c2s: [u8; KEY_LEN],
+    pub s2c: [u8; KEY_LEN],
+}
+
+impl Keys {
+    /// Exports both keys with `export(output, label, context)`, the TLS exporter of the
+    /// NTS-KE connection, e.g. `ConnectionCommon::export_keying_material`.
+    pub fn export<E>(
+        mut export: impl FnMut(&mut [u8], &[u8], &[u8]) -> Result<(), E>,
+    ) -> Result<Self, E> {
+        let mut keys = Self {
+            c2s: [0; KEY_LEN],
+            s2c: [0; KEY_LEN],
+        };
+        // RFC8915 section 5.1: the context is the protocol, the AEAD and the direction
+        let mut context = [0; 5];
+        context[..2].copy_from_slice(&NTPV4.to_be_bytes());
+        context[2..4].copy_from_slice(&AEAD_AES_SIV_CMAC_256.to_be_bytes());
+        export(&mut keys.c2s, EXPORTER_LABEL, &context)?;
+        context[4] = 1;
+        export(&mut keys.s2c, EXPORTER_LABEL, &context)?;
+        Ok(keys)
+    }
+}
+
+/// The keys and cookies agreed with an NTS-KE server, to ask its NTP server for the time.
+#[derive(Debug)]
+pub struct NtsSession {
+    ntp_server: String,
+    ntp_port: u16,
+    keys: Keys,
+    cookies: Vec<Vec<u8>>,
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+}
+
+impl NtsSession {
+    /// The session of a key exchange with the NTS-KE server `host`: its `response`, and the
+    /// `keys` exported from the same TLS connection.
+    pub fn new(host: &str, response: KeResponse, keys: Keys) -> Self {
+        Self {
+            ntp_server: response.ntp_server.unwrap_or_else(|| host.to_string()),
+            ntp_port: response.ntp_port.unwrap_or(sntp::PORT),
+            keys,
+            cookies: response.cookies,
+            #[cfg(feature = "std")]
+            started: std::time::Instant::now(),
+        }
+    }
+
+    /// The NTPv4 server and port that the NTS-KE server pointed to.
+    pub fn ntp_server(&self) -> (&str, u16) {
+        (&self.ntp_server, self.ntp_port)
+    }
+
+    /// The cookies left; run the key exchange again when there are none.
+    pub fn cookies(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// An authenticated request sent at `now`, in microseconds of the caller's monotonic
+    /// clock. It takes a cookie, which its reply replaces.
+    pub fn request(&mut self, now: u64) -> Result<NtsRequest, Error> {
+        // a cookie is never sent twice, so that requests can't be linked (RFC8915 section 6)
+        let cookie = self.cookies.pop().ok_or(Error::NoCookie)?;
+
+        let mut nonce = [0; 8];
+        let mut unique_id = [0; UNIQUE_ID_LEN];
+        let mut aead_nonce = [0; NONCE_LEN];
+        OsRng
+            .try_fill_bytes(&mut nonce)
+            .and_then(|_| OsRng.try_fill_bytes(&mut unique_id))
+            .and_then(|_| OsRng.try_fill_bytes(&mut aead_nonce))
+            .map_err(|_| Error::Random)?;
+        let request = Request::new(nonce, now);
+
+        let mut packet = request.packet().to_vec();
+        extension(&mut packet, UNIQUE_ID, &unique_id);
+        extension(&mut packet, COOKIE, &cookie);
+        // ask for as many new cookies as are missing
+        for _ in self.cookies.len() + 1..MAX_COOKIES {
+            extension(&mut packet, COOKIE_PLACEHOLDER, &vec![0; cookie.len()]);
+        }
+        let tag = Aes128SivAead::new(&self.keys.c2s.into())
+            .encrypt(
+                Nonce::from_slice(&aead_nonce),
+                Payload {
+                    msg: &[],
+                    aad: &packet,
+                },
+            )
+            .map_err(|_| Error::Unauthenticated)?;
+        let mut authenticator = Vec::with_capacity(4 + NONCE_LEN + tag.len());
+        authenticator.extend_from_slice(&(NONCE_LEN as u16).to_be_bytes());
+        authenticator.extend_from_slice(&(tag.len() as u16).to_be_bytes());
+        authenticator.extend_from_slice(&aead_nonce);
+        authenticator.extend_from_slice(&tag);
+        extension(&mut packet, AUTHENTICATOR, &authenticator);
+
+        Ok(NtsRequest {
+            request,
+            unique_id,
+            packet,
+        })
+    }
+
+    /// Checks `reply` to `request`, received at `received` on the clock of
+    /// [`Self::request`], and keeps the cookies that it brings.
+    pub fn reply(
+        &mut self,
+        request: &NtsRequest,
+        reply: &[u8],
+        received: u64,
+    ) -> Result<Sample, Error> {
+        let result = request.request.reply(reply, received);
+        if let Err(
+            sntp::Error::Truncated | sntp::Error::NotServerReply | sntp::Error::OriginMismatch,
+        ) = result
+        {
+            return result.map_err(Error::Sntp);
+        }
+
+        let mut offset = sntp::PACKET_LEN;
+        let mut authenticated = None;
+        let mut echoed = false;
+        while offset < reply.len() {
+            let (typ, body, next) = parse_extension(reply, offset)?;
+            match typ {
+                UNIQUE_ID => echoed = body == request.unique_id,
+                AUTHENTICATOR => {
+                    authenticated = Some(self.open(&reply[..offset], body)?);
+                    // RFC8915 section 5.7: what follows isn't authenticated
+                    break;
+                }
+                _ => {}
+            }
+            offset = next;
+        }
+        if !echoed {
+            return Err(Error::Unauthenticated);
+        }
+        // an NTS NAK (RFC8915 section 5.7) isn't authenticated, but it echoes the unique
+        // identifier; any other Kiss-o'-Death must be authenticated like a time
+        if result == Err(sntp::Error::KissOfDeath(*b"NTSN")) {
+            self.cookies.clear();
+            return result.map_err(Error::Sntp);
+        }
+        let plaintext = authenticated.ok_or(Error::Unauthenticated)?;
+
+        let sample = result.map_err(Error::Sntp)?;
+        let mut offset = 0;
+        while offset < plaintext.len() {
+            let (typ, body, next) = parse_extension(&plaintext, offset)?;
+            if typ == COOKIE && self.cookies.len() < MAX_COOKIES {
+                self.cookies.push(body.to_vec());
+            }
+            offset = next;
+        }
+        Ok(sample)
+    }
+
+    /// Decrypts the body of the authenticator extension field, with everything before it as
+    /// associated data.
+    fn open(&self, aad: &[u8], body: &[u8]) -> Result<Vec<u8>, Error> {
+        let [n0, n1, c0, c1, body @ ..] = body else {
+            return Err(Error::Unauthenticated);
+        };
+        let nonce_len = usize::from(u16::from_be_bytes([*n0, *n1]));
+        let ciphertext_len = usize::from(u16::from_be_bytes([*c0, *c1]));
+        // `Aes128SivAead` takes 16-byte nonces, as do the servers
+        if nonce_len != NONCE_LEN {
+            return Err(Error::Unauthenticated);
+        }
+        let nonce = body.get(..NONCE_LEN).ok_or(Error::Unauthenticated)?;
+        let ciphertext = body
+            .get(NONCE_LEN..NONCE_LEN + ciphertext_len)
+            .ok_or(Error::Unauthenticated)?;
+
+        Aes128SivAead::new(&self.keys.s2c.into())
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| Error::Unauthenticated)
+    }
+}
+
+/// An NTP request of [`NtsSession::request`], waiting for its reply.
+#[derive(Debug)]
+pub struct NtsRequest {
+    request: Request,
+    unique_id: [u8; UNIQUE_ID_LEN],
+    packet: Vec<u8>,
+}
+
+impl NtsRequest {
+    /// The NTP packet to send, up to [`PACKET_LEN`] bytes.
+    pub fn packet(&self) -> &[u8] {
+        &self.packet
+    }
+}
+
+/// The adapter over `std::net`: NTS-KE over a `TcpStream`, and NTP over a `UdpSocket`.
+#[cfg(feature = "std")]
+mod sockets {
+    use alloc::sync::Arc;
+    use std::io::{self, Read, Write};
+    use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+    use std::time::Instant;
+
+    use rustls::pki_types::ServerName;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+
+    use super::*;
+    use crate::exchange::{self, Transport};
+
+    impl NtsSession {
+        /// Runs NTS-KE with `host`, authenticating it with `roots`.
+        pub fn key_exchange(host: &str, port: u16, roots: RootCertStore) -> Result<Self, Error> {
+            let mut config = ClientConfig::builder_with_provider(Arc::new(crate::provider()))
+                .with_protocol_versions(&[&rustls::version::TLS13])?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            config.alpn_protocols = vec![ALPN.to_vec()];
+
+            let server_name = ServerName::try_from(host.to_string())
+                .map_err(|_| Error::KeProtocol("invalid server name"))?;
+            let conn = ClientConnection::new(Arc::new(config), server_name)?;
+            let sock = TcpStream::connect((host, port))?;
+            sock.set_read_timeout(Some(TIMEOUT))?;
+            sock.set_write_timeout(Some(TIMEOUT))?;
+            let mut tls = StreamOwned::new(conn, sock);
+
+            tls.write_all(&ke_request())?;
+            tls.flush()?;
+
+            let mut received = Vec::new();
+            let response = loop {
+                let mut buf = [0; 1024];
+                let len = tls.read(&mut buf)?;
+                if len == 0 {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                received.extend_from_slice(&buf[..len]);
+                if let Some(response) = KeResponse::parse(&received)? {
+                    break response;
+                }
+            };
+            let keys = Keys::export(|output, label, context| {
+                tls.conn
+                    .export_keying_material(output, label, Some(context))
+                    .map(|_| ())
+            })?;
+
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+            Ok(Self::new(host, response, keys))
+        }
+
+        /// Asks the NTPv4 server for the time, up to [`ATTEMPTS`] times.
+        ///
+        /// [`Sample::at`] counts the microseconds since the key exchange.
+        pub fn query(&mut self) -> Result<Sample, Error> {
+            let server = (self.ntp_server.as_str(), self.ntp_port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or(io::Error::from(io::ErrorKind::NotFound))?;
+            let sock = UdpSocket::bind(match server {
+                SocketAddr::V4(_) => SocketAddr::from(([0; 4], 0)),
+                SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+            })?;
+            sock.connect(server)?;
+
+            let socket = Socket(&sock, self.started);
+            let mut error = Error::NoCookie;
+            for _ in 0..ATTEMPTS {
+                match exchange::block_on(exchange::query_nts(&socket, self, TIMEOUT)) {
+                    Ok(sample) => return Ok(sample),
+                    Err(e @ (Error::NoCookie | Error::Sntp(sntp::Error::KissOfDeath(_)))) => {
+                        return Err(e)
+                    }
+                    Err(e) => error = e,
+                }
+            }
+            Err(error)
+        }
+    }
+
+    /// The socket of [`NtsSession::query`], and the clock of the session.
+    struct Socket<'a>(&'a UdpSocket, Instant);
+
+    impl Transport for Socket<'_> {
+        type Error = Error;
+
+        fn now(&self) -> u64 {
+            self.1.elapsed().as_micros() as u64
+        }
+
+        async fn send(&self, packet: &[u8]) -> Result<(), Error> {
+            self.0.send(packet)?;
+            Ok(())
+        }
+
+        async fn recv(&self, buffer: &mut [u8], deadline: u64) -> Result<usize, Error> {
+            let remaining = deadline.saturating_sub(self.now());
+            if remaining == 0 {
+                return Err(io::Error::from(io::ErrorKind::TimedOut).into());
+            }
+            self.0
+                .set_read_timeout(Some(Duration::from_micros(remaining)))?;
+            Ok(self.0.recv(buffer)?)
+        }
+    }
+
+    impl From<io::Error> for Error {
+        fn from(v: io::Error) -> Self {
+            Self::Io(v)
+        }
+    }
+
+    impl From<rustls::Error> for Error {
+        fn from(v: rustls::Error) -> Self {
+            Self::Tls(v)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "std")]
+    Tls(rustls::Error),
+    /// The NTS-KE server answered with an Error record, with its code.
+    KeServer(u16),
+    KeProtocol(&'static str),
+    /// There are no cookies left, or the NTS-KE server sent none.
+    NoCookie,
+    /// A reply that isn't for our request or whose authenticator is missing or wrong.
+    Unauthenticated,
+    Random,
+    Sntp(sntp::Error),
+}
+
+impl From<sntp::Error> for Error {
+    fn from(e: sntp::Error) -> Self {
+        Self::Sntp(e)
+    }
+}
+
+fn record(buf: &mut Vec<u8>, typ: u16, body: &[u8]) {
+    buf.extend_from_slice(&typ.to_be_bytes());
+    buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    buf.extend_from_slice(body);
+}
+
+fn u16_body(body: &[u8]) -> Result<u16, Error> {
+    body.try_into()
+        .map(u16::from_be_bytes)
+        .map_err(|_| Error::KeProtocol("record body isn't a 16-bit integer"))
+}
+
+/// Appends an extension field, zero-padded to a multiple of 4 bytes (RFC7822).
+fn extension(packet: &mut Vec<u8>, typ: u16, body: &[u8]) {
+    let len = (4 + body.len()).next_multiple_of(4);
+    packet.extend_from_slice(&typ.to_be_bytes());
+    packet.extend_from_slice(&(len as u16).to_be_bytes());
+    packet.extend_from_slice(body);
+    packet.resize(packet.len() + len - 4 - body.len(), 0);
+}
+
+/// The type and body of the extension field at `offset`, and the offset of the next one.
+fn parse_extension(packet: &[u8], offset: usize) -> Result<(u16, &[u8], usize), Error> {
+    let header = packet
+        .get(offset..offset + 4)
+        .ok_or(Error::Unauthenticated)?;
+    let typ = u16::from_be_bytes([header[0], header[1]]);
+    let len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    if len < 4 || len % 4 != 0 {
+        return Err(Error::Unauthenticated);
+    }
+    let body = packet
+        .get(offset + 4..offset + len)
+        .ok_or(Error::Unauthenticated)?;
+    Ok((typ, body, offset + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response with NTPv4, AEAD_AES_SIV_CMAC_256 and `records`, then End of Message.
+    fn ke_response(records: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut response = Vec::new();
+        record(&mut response, CRITICAL | NEXT_PROTOCOL, &[0, 0]);
+        record(&mut response, CRITICAL | AEAD_ALGORITHM, &[0, 15]);
+        for &(typ, body) in records {
+            record(&mut response, typ, body);
+        }
+        record(&mut response, CRITICAL | END_OF_MESSAGE, &[]);
+        response
+    }
+
+    #[test]
+    fn ke_response_in_pieces() {
+        let response = ke_response(&[
+            (KE_WARNING, &[0, 1]),
+            (CRITICAL | NEW_COOKIE, &[1; 100]),
+            (CRITICAL | NEW_COOKIE, &[2; 100]),
+            (CRITICAL | SERVER, b"ntp.example"),
+            (CRITICAL | PORT, &[0, 124]),
+        ]);
+        for len in 0..response.len() {
+            assert!(matches!(KeResponse::parse(&response[..len]), Ok(None)));
+        }
+
+        let parsed = KeResponse::parse(&response).unwrap().unwrap();
+        assert_eq!(parsed.ntp_server.as_deref(), Some("ntp.example"));
+        assert_eq!(parsed.ntp_port, Some(124));
+        assert_eq!(parsed.cookies, [[1; 100], [2; 100]]);
+
+        let session = NtsSession::new("nts.example", parsed, keys());
+        assert_eq!(session.ntp_server(), ("ntp.example", 124));
+        assert_eq!(session.cookies(), 2);
+    }
+
+    #[test]
+    fn ke_response_defaults_to_the_ke_server() {
+        let response = ke_response(&[(NEW_COOKIE, &[1; 100])]);
+        let parsed = KeResponse::parse(&response).unwrap().unwrap();
+        let session = NtsSession::new("nts.example", parsed, keys());
+        assert_eq!(session.ntp_server(), ("nts.example", sntp::PORT));
+    }
+
+    #[test]
+    fn ke_response_errors() {
+        let cookie: (u16, &[u8]) = (NEW_COOKIE, &[1; 100]);
+        assert!(matches!(
+            KeResponse::parse(&ke_response(&[(CRITICAL | KE_ERROR, &[0, 1]), cookie])),
+            Err(Error::KeServer(1))
+        ));
+        assert!(matches!(
+            KeResponse::parse(&ke_response(&[(CRITICAL | 0x4242, b"x"), cookie])),
+            Err(Error::KeProtocol("unknown critical record"))
+        ));
+        assert!(matches!(
+            KeResponse::parse(&ke_response(&[])),
+            Err(Error::NoCookie)
+        ));
+        // AES-SIV-CMAC-512 instead
+        let mut response = ke_response(&[cookie]);
+        response[11] = 16;
+        assert!(matches!(
+            KeResponse::parse(&response),
+            Err(Error::KeProtocol("AEAD_AES_SIV_CMAC_256 wasn't selected"))
+        ));
+    }
+
+    #[test]
+    fn ke_request_records() {
+        assert_eq!(
+            ke_request(),
+            [0x80, 0x01, 0, 2, 0, 0, 0x80, 0x04, 0, 2, 0, 15, 0x80, 0x00, 0, 0]
+        );
+    }
+
+    #[test]
+    fn keys_context() {
+        let mut contexts = Vec::new();
+        let keys = Keys::export(|output: &mut [u8], label: &[u8], context: &[u8]| {
+            assert_eq!(label, EXPORTER_LABEL);
+            output.fill(context[4]);
+            contexts.push(context.to_vec());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(contexts, [[0, 0, 0, 15, 0], [0, 0, 0, 15, 1]]);
+        assert_eq!((keys.c2s, keys.s2c), ([0; KEY_LEN], [1; KEY_LEN]));
+    }
+
+    fn keys() -> Keys {
+        Keys {
+            c2s: [1; KEY_LEN],
+            s2c: [2; KEY_LEN],
+        }
+    }
+
+    /// The reply of a server with `keys()` to `packet`, with a new cookie.
+    fn reply_to(packet: &[u8]) -> Vec<u8> {
+        let mut reply = vec![0; sntp::PACKET_LEN];
+        reply[..2].copy_from_slice(&[0x24, 2]);
+        reply[24..32].copy_from_slice(&packet[40..48]);
+        let now = (1_717_200_000u64 + 2_208_988_800) << 32;
+        reply[32..40].copy_from_slice(&now.to_be_bytes());
+        reply[40..48].copy_from_slice(&now.to_be_bytes());
+        let (_, unique_id, _) = parse_extension(packet, sntp::PACKET_LEN).unwrap();
+        extension(&mut reply, UNIQUE_ID, unique_id);
+
+        let mut cookie = Vec::new();
+        extension(&mut cookie, COOKIE, &[9; 100]);
+        let nonce = [3; NONCE_LEN];
+        let ciphertext = Aes128SivAead::new(&keys().s2c.into())
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &cookie,
+                    aad: &reply,
+                },
+            )
+            .unwrap();
+        let mut authenticator = Vec::new();
+        authenticator.extend_from_slice(&(NONCE_LEN as u16).to_be_bytes());
+        authenticator.extend_from_slice(&(ciphertext.len() as u16).to_be_bytes());
+        authenticator.extend_from_slice(&nonce);
+        authenticator.extend_from_slice(&ciphertext);
+        extension(&mut reply, AUTHENTICATOR, &authenticator);
+        reply
+    }
+
+    #[test]
+    fn request_and_reply() {
+        let response = KeResponse {
+            cookies: vec![vec![1; 100], vec![2; 100]],
+            ..KeResponse::default()
+        };
+        let mut session = NtsSession::new("nts.example", response, keys());
+
+        let request = session.request(1_000).unwrap();
+        assert_eq!(session.cookies(), 1);
+        let packet = request.packet();
+        assert!(packet.len() <= PACKET_LEN);
+        // the unique identifier, the cookie, 6 placeholders and the authenticator
+        let mut fields = Vec::new();
+        let mut offset = sntp::PACKET_LEN;
+        while offset < packet.len() {
+            let (typ, body, next) = parse_extension(packet, offset).unwrap();
+            fields.push(typ);
+            if typ == COOKIE {
+                assert_eq!(body, [2; 100]);
+            }
+            offset = next;
+        }
+        assert_eq!(fields[..2], [UNIQUE_ID, COOKIE]);
+        assert_eq!(fields[2..8], [COOKIE_PLACEHOLDER; 6]);
+        assert_eq!(fields[8..], [AUTHENTICATOR]);
+
+        let mut tampered = reply_to(packet);
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(
+            session.reply(&request, &tampered, 2_000),
+            Err(Error::Unauthenticated)
+        ));
+        assert_eq!(session.cookies(), 1);
+
+        let sample = session.reply(&request, &reply_to(packet), 2_000).unwrap();
+        assert_eq!(sample.unix.as_secs(), 1_717_200_000);
+        assert_eq!(session.cookies(), 2);
+        assert_eq!(session.cookies.last().unwrap(), &[9; 100]);
+
+        // a reply to another request is dropped before the authenticator is checked
+        let other = session.request(3_000).unwrap();
+        assert!(matches!(
+            session.reply(&other, &reply_to(packet), 4_000),
+            Err(Error::Sntp(sntp::Error::OriginMismatch))
+        ));
+    }
+}
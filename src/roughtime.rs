@@ -0,0 +1,285 @@
+//! A sans-I/O Roughtime client.
+//!
+//! It builds requests and verifies the signed replies of servers whose Ed25519 public key
+//! is known, without trusting the network or a previous time. The sockets and the clocks
+//! are left to the caller, like in [`crate::sntp`].
+//!
+//! This is the Roughtime protocol of the original Google implementation (the
+//! `RoughTime v1` signature contexts, SHA-512 Merkle trees and microsecond timestamps),
+//! not the drafts of the IETF NTP working group.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha512};
+
+pub const PORT: u16 = 2002;
+/// Requests are padded to 1024 bytes, so that replies are never larger than them.
+pub const REQUEST_LEN: usize = 1024;
+pub const NONCE_LEN: usize = 64;
+
+const DELEGATION_CONTEXT: &[u8] = b"RoughTime v1 delegation signature--\x00";
+const RESPONSE_CONTEXT: &[u8] = b"RoughTime v1 response signature\x00";
+/// Servers answer batches of at most 2^32 requests.
+const MAX_PATH_LEN: usize = 32;
+
+type Tag = [u8; 4];
+
+const NONC: Tag = *b"NONC";
+const PAD: Tag = *b"PAD\xff";
+const SIG: Tag = *b"SIG\x00";
+const PATH: Tag = *b"PATH";
+const SREP: Tag = *b"SREP";
+const CERT: Tag = *b"CERT";
+const INDX: Tag = *b"INDX";
+const ROOT: Tag = *b"ROOT";
+const MIDP: Tag = *b"MIDP";
+const RADI: Tag = *b"RADI";
+const DELE: Tag = *b"DELE";
+const MINT: Tag = *b"MINT";
+const MAXT: Tag = *b"MAXT";
+const PUBK: Tag = *b"PUBK";
+
+/// One request to a server, kept to verify its reply.
+#[derive(Clone, Debug)]
+pub struct Request {
+    nonce: [u8; NONCE_LEN],
+    sent: u64,
+}
+
+impl Request {
+    /// A request sent when the monotonic clock reads `sent` microseconds.
+    ///
+    /// The server signs `nonce` together with the time, so it must be random: a reply
+    /// can't be replayed to another request.
+    pub fn new(nonce: [u8; NONCE_LEN], sent: u64) -> Self {
+        Self { nonce, sent }
+    }
+
+    pub fn packet(&self) -> [u8; REQUEST_LEN] {
+        let mut packet = [0; REQUEST_LEN];
+        // two tags, the offset of the second value, the tags, the nonce, then the padding
+        packet[0..4].copy_from_slice(&2u32.to_le_bytes());
+        packet[4..8].copy_from_slice(&(NONCE_LEN as u32).to_le_bytes());
+        packet[8..12].copy_from_slice(&NONC);
+        packet[12..16].copy_from_slice(&PAD);
+        packet[16..16 + NONCE_LEN].copy_from_slice(&self.nonce);
+        packet
+    }
+
+    /// Verifies `reply` with the long-term `public_key` of the server, received when the
+    /// monotonic clock read `received` microseconds.
+    ///
+    /// [`Error::NonceMismatch`] means the reply belongs to another request (or was forged)
+    /// and should be ignored.
+    pub fn reply(
+        &self,
+        reply: &[u8],
+        public_key: &[u8; 32],
+        received: u64,
+    ) -> Result<Sample, Error> {
+        let reply = Message::parse(reply)?;
+        let signed = reply.get(SREP)?;
+        let response = Message::parse(signed)?;
+
+        // the reply signs the root of a Merkle tree over the nonces of a batch of requests
+        let index = u32::from_le_bytes(*reply.array(INDX)?);
+        let path = reply.get(PATH)?;
+        if path.len() % 64 != 0 || path.len() / 64 > MAX_PATH_LEN {
+            return Err(Error::Malformed);
+        }
+        if merkle_root(&self.nonce, index, path) != *response.array::<64>(ROOT)? {
+            return Err(Error::NonceMismatch);
+        }
+
+        // the long-term key signs a delegation to an online key, which signs the reply
+        let certificate = Message::parse(reply.get(CERT)?)?;
+        let delegation = certificate.get(DELE)?;
+        verify(
+            public_key,
+            DELEGATION_CONTEXT,
+            delegation,
+            certificate.array(SIG)?,
+        )
+        .map_err(|_| Error::BadDelegation)?;
+        let delegation = Message::parse(delegation)?;
+        verify(
+            delegation.array(PUBK)?,
+            RESPONSE_CONTEXT,
+            signed,
+            reply.array(SIG)?,
+        )
+        .map_err(|_| Error::BadSignature)?;
+
+        let midpoint = u64::from_le_bytes(*response.array(MIDP)?);
+        let radius = u32::from_le_bytes(*response.array(RADI)?);
+        let not_before = u64::from_le_bytes(*delegation.array(MINT)?);
+        let not_after = u64::from_le_bytes(*delegation.array(MAXT)?);
+        if !(not_before..=not_after).contains(&midpoint) {
+            return Err(Error::OutsideDelegation);
+        }
+
+        // the server read its clock at some point of the round trip
+        let round_trip = Duration::from_micros(received.saturating_sub(self.sent));
+        Ok(Sample {
+            unix: Duration::from_micros(midpoint) + round_trip / 2,
+            radius: Duration::from_micros(radius.into()) + round_trip / 2,
+            at: received,
+        })
+    }
+}
+
+/// The time from a verified reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// The Unix time when the reply was received.
+    pub unix: Duration,
+    /// How far off `unix` can be: the radius of the server plus half the round trip.
+    pub radius: Duration,
+    /// The monotonic clock reading passed to [`Request::reply`].
+    pub at: u64,
+}
+
+/// The interval that all `samples` agree on, at the monotonic clock reading of the latest
+/// one, or `None` if there are none or two of them don't overlap (one of the servers is
+/// wrong or lying).
+pub fn combine(samples: &[Sample]) -> Option<Sample> {
+    let at = samples.iter().map(|sample| sample.at).max()?;
+    let mut earliest = Duration::ZERO;
+    let mut latest = Duration::MAX;
+    for sample in samples {
+        let unix = sample.unix + Duration::from_micros(at - sample.at);
+        earliest = earliest.max(unix.saturating_sub(sample.radius));
+        latest = latest.min(unix.saturating_add(sample.radius));
+    }
+    if earliest > latest {
+        return None;
+    }
+    let radius = (latest - earliest) / 2;
+    Some(Sample {
+        unix: earliest + radius,
+        radius,
+        at,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Not a Roughtime message, or a tag is missing or has the wrong length.
+    Malformed,
+    /// The nonce of the request isn't in the signed Merkle tree.
+    NonceMismatch,
+    /// The delegation isn't signed by the public key of the server.
+    BadDelegation,
+    /// The reply isn't signed by the delegated key.
+    BadSignature,
+    /// The time is outside the validity of the delegated key.
+    OutsideDelegation,
+}
+
+/// A Roughtime message: a list of tags and values, with the tags in increasing order.
+struct Message<'a> {
+    /// The offsets of the values but the first.
+    offsets: &'a [u8],
+    tags: &'a [u8],
+    values: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let count = u32::from_le_bytes(*array(bytes, 0)?) as usize;
+        let header_len = count.checked_mul(8).ok_or(Error::Malformed)?.max(4);
+        // values are padded to multiples of 4 bytes
+        if bytes.len() < header_len || bytes.len() & 3 != 0 {
+            return Err(Error::Malformed);
+        }
+        let message = Self {
+            offsets: &bytes[4..4 * count.max(1)],
+            tags: &bytes[4 * count.max(1)..header_len],
+            values: &bytes[header_len..],
+        };
+
+        let mut previous = (0, None);
+        for i in 0..count {
+            let offset = message.offset(i)?;
+            let tag = u32::from_le_bytes(*array(message.tags, 4 * i)?);
+            if offset < previous.0
+                || offset > message.values.len()
+                || offset % 4 != 0
+                || previous.1.is_some_and(|previous| tag <= previous)
+            {
+                return Err(Error::Malformed);
+            }
+            previous = (offset, Some(tag));
+        }
+        Ok(message)
+    }
+
+    fn offset(&self, i: usize) -> Result<usize, Error> {
+        match i {
+            0 => Ok(0),
+            i => Ok(u32::from_le_bytes(*array(self.offsets, 4 * (i - 1))?) as usize),
+        }
+    }
+
+    fn get(&self, tag: Tag) -> Result<&'a [u8], Error> {
+        let i = self
+            .tags
+            .chunks_exact(4)
+            .position(|t| t == tag)
+            .ok_or(Error::Malformed)?;
+        let end = if i + 1 < self.tags.len() / 4 {
+            self.offset(i + 1)?
+        } else {
+            self.values.len()
+        };
+        Ok(&self.values[self.offset(i)?..end])
+    }
+
+    fn array<const N: usize>(&self, tag: Tag) -> Result<&'a [u8; N], Error> {
+        self.get(tag)?.try_into().map_err(|_| Error::Malformed)
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8], offset: usize) -> Result<&[u8; N], Error> {
+    bytes
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(Error::Malformed)
+}
+
+/// The root of the tree with `nonce` at leaf `index`, given the siblings on the way up.
+fn merkle_root(nonce: &[u8; NONCE_LEN], mut index: u32, path: &[u8]) -> [u8; 64] {
+    let mut hash: [u8; 64] = Sha512::new()
+        .chain_update([0])
+        .chain_update(nonce)
+        .finalize()
+        .into();
+    for sibling in path.chunks_exact(64) {
+        let (left, right) = match index & 1 {
+            0 => (&hash[..], sibling),
+            _ => (sibling, &hash[..]),
+        };
+        hash = Sha512::new()
+            .chain_update([1])
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .into();
+        index >>= 1;
+    }
+    hash
+}
+
+fn verify(
+    public_key: &[u8; 32],
+    context: &[u8],
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<(), ed25519_dalek::SignatureError> {
+    let mut signed = Vec::with_capacity(context.len() + message.len());
+    signed.extend_from_slice(context);
+    signed.extend_from_slice(message);
+    VerifyingKey::from_bytes(public_key)?.verify_strict(&signed, &Signature::from_bytes(signature))
+}
@@ -2,34 +2,53 @@ use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
+#[cfg(feature = "ecdsa-sign")]
+use pkcs8::AssociatedOid;
 use pkcs8::DecodePrivateKey;
+#[cfg(feature = "rsa-sign")]
+use rsa::pkcs1::DecodeRsaPrivateKey;
 use rustls::pki_types::PrivateKeyDer;
 use rustls::sign::{Signer, SigningKey};
 use rustls::{SignatureAlgorithm, SignatureScheme};
+#[cfg(feature = "rsa-sign")]
+use sha2::Digest;
+#[cfg(feature = "ed25519-sign")]
+use signature::Signer as _;
+#[cfg(feature = "ecdsa-sign")]
 use signature::{RandomizedSigner, SignatureEncoding};
 
+#[cfg(feature = "ecdsa-sign")]
 #[derive(Clone, Debug)]
 pub struct EcdsaSigningKeyP256 {
     key: Arc<p256::ecdsa::SigningKey>,
     scheme: SignatureScheme,
 }
 
+#[cfg(feature = "ecdsa-sign")]
 impl TryFrom<PrivateKeyDer<'_>> for EcdsaSigningKeyP256 {
-    type Error = pkcs8::Error;
+    type Error = rustls::Error;
 
     fn try_from(value: PrivateKeyDer<'_>) -> Result<Self, Self::Error> {
-        match value {
+        let key = match value {
             PrivateKeyDer::Pkcs8(der) => {
-                p256::ecdsa::SigningKey::from_pkcs8_der(der.secret_pkcs8_der()).map(|kp| Self {
-                    key: Arc::new(kp),
-                    scheme: SignatureScheme::ECDSA_NISTP256_SHA256,
-                })
+                p256::ecdsa::SigningKey::from_pkcs8_der(der.secret_pkcs8_der())
+                    .map_err(key_error)?
             }
-            _ => panic!("unsupported private key format"),
-        }
+            PrivateKeyDer::Sec1(der) => {
+                let key = sec1_key(der.secret_sec1_der(), p256::NistP256::OID)?;
+                p256::SecretKey::try_from(key).map_err(key_error)?.into()
+            }
+            _ => return Err(unsupported_format()),
+        };
+
+        Ok(Self {
+            key: Arc::new(key),
+            scheme: SignatureScheme::ECDSA_NISTP256_SHA256,
+        })
     }
 }
 
+#[cfg(feature = "ecdsa-sign")]
 impl SigningKey for EcdsaSigningKeyP256 {
     fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
         if offered.contains(&self.scheme) {
@@ -44,6 +63,7 @@ impl SigningKey for EcdsaSigningKeyP256 {
     }
 }
 
+#[cfg(feature = "ecdsa-sign")]
 impl Signer for EcdsaSigningKeyP256 {
     fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
         self.key
@@ -56,3 +76,240 @@ impl Signer for EcdsaSigningKeyP256 {
         self.scheme
     }
 }
+
+#[cfg(feature = "ecdsa-sign")]
+#[derive(Clone, Debug)]
+pub struct EcdsaSigningKeyP384 {
+    key: Arc<p384::ecdsa::SigningKey>,
+    scheme: SignatureScheme,
+}
+
+#[cfg(feature = "ecdsa-sign")]
+impl TryFrom<PrivateKeyDer<'_>> for EcdsaSigningKeyP384 {
+    type Error = rustls::Error;
+
+    fn try_from(value: PrivateKeyDer<'_>) -> Result<Self, Self::Error> {
+        let key = match value {
+            PrivateKeyDer::Pkcs8(der) => {
+                p384::ecdsa::SigningKey::from_pkcs8_der(der.secret_pkcs8_der())
+                    .map_err(key_error)?
+            }
+            PrivateKeyDer::Sec1(der) => {
+                let key = sec1_key(der.secret_sec1_der(), p384::NistP384::OID)?;
+                p384::SecretKey::try_from(key).map_err(key_error)?.into()
+            }
+            _ => return Err(unsupported_format()),
+        };
+
+        Ok(Self {
+            key: Arc::new(key),
+            scheme: SignatureScheme::ECDSA_NISTP384_SHA384,
+        })
+    }
+}
+
+#[cfg(feature = "ecdsa-sign")]
+impl SigningKey for EcdsaSigningKeyP384 {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&self.scheme) {
+            Some(Box::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::ECDSA
+    }
+}
+
+#[cfg(feature = "ecdsa-sign")]
+impl Signer for EcdsaSigningKeyP384 {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.key
+            .try_sign_with_rng(&mut rand_core::OsRng, message)
+            .map_err(|_| rustls::Error::General("signing failed".into()))
+            .map(|sig: p384::ecdsa::DerSignature| sig.to_vec())
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+#[cfg(feature = "ed25519-sign")]
+#[derive(Clone, Debug)]
+pub struct Ed25519SigningKey {
+    key: Arc<ed25519_dalek::SigningKey>,
+    scheme: SignatureScheme,
+}
+
+#[cfg(feature = "ed25519-sign")]
+impl TryFrom<PrivateKeyDer<'_>> for Ed25519SigningKey {
+    type Error = rustls::Error;
+
+    fn try_from(value: PrivateKeyDer<'_>) -> Result<Self, Self::Error> {
+        match value {
+            PrivateKeyDer::Pkcs8(der) => {
+                ed25519_dalek::SigningKey::from_pkcs8_der(der.secret_pkcs8_der())
+                    .map(|kp| Self {
+                        key: Arc::new(kp),
+                        scheme: SignatureScheme::ED25519,
+                    })
+                    .map_err(key_error)
+            }
+            _ => Err(unsupported_format()),
+        }
+    }
+}
+
+#[cfg(feature = "ed25519-sign")]
+impl SigningKey for Ed25519SigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&self.scheme) {
+            Some(Box::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::ED25519
+    }
+}
+
+#[cfg(feature = "ed25519-sign")]
+impl Signer for Ed25519SigningKey {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.key
+            .try_sign(message)
+            .map_err(|_| rustls::Error::General("signing failed".into()))
+            .map(|sig| sig.to_vec())
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+#[cfg(feature = "rsa-sign")]
+#[derive(Clone, Debug)]
+pub struct RsaSigningKey {
+    key: Arc<rsa::RsaPrivateKey>,
+}
+
+#[cfg(feature = "rsa-sign")]
+impl TryFrom<PrivateKeyDer<'_>> for RsaSigningKey {
+    type Error = rustls::Error;
+
+    fn try_from(value: PrivateKeyDer<'_>) -> Result<Self, Self::Error> {
+        let key = match value {
+            PrivateKeyDer::Pkcs8(der) => {
+                rsa::RsaPrivateKey::from_pkcs8_der(der.secret_pkcs8_der()).map_err(key_error)?
+            }
+            PrivateKeyDer::Pkcs1(der) => {
+                rsa::RsaPrivateKey::from_pkcs1_der(der.secret_pkcs1_der()).map_err(key_error)?
+            }
+            _ => return Err(unsupported_format()),
+        };
+
+        Ok(Self { key: Arc::new(key) })
+    }
+}
+
+#[cfg(feature = "rsa-sign")]
+impl SigningKey for RsaSigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        RSA_SCHEMES
+            .iter()
+            .find(|scheme| offered.contains(scheme))
+            .map(|&scheme| {
+                Box::new(RsaSigner {
+                    key: self.key.clone(),
+                    scheme,
+                }) as Box<dyn Signer>
+            })
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::RSA
+    }
+}
+
+/// Schemes an RSA key can sign with, in order of preference.
+#[cfg(feature = "rsa-sign")]
+static RSA_SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::RSA_PSS_SHA384,
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA256,
+];
+
+#[cfg(feature = "rsa-sign")]
+#[derive(Debug)]
+struct RsaSigner {
+    key: Arc<rsa::RsaPrivateKey>,
+    scheme: SignatureScheme,
+}
+
+#[cfg(feature = "rsa-sign")]
+impl Signer for RsaSigner {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        let rng = &mut rand_core::OsRng;
+        match self.scheme {
+            SignatureScheme::RSA_PSS_SHA384 => self.key.sign_with_rng(
+                rng,
+                rsa::Pss::new::<sha2::Sha384>(),
+                &sha2::Sha384::digest(message),
+            ),
+            SignatureScheme::RSA_PSS_SHA256 => self.key.sign_with_rng(
+                rng,
+                rsa::Pss::new::<sha2::Sha256>(),
+                &sha2::Sha256::digest(message),
+            ),
+            SignatureScheme::RSA_PKCS1_SHA384 => self.key.sign_with_rng(
+                rng,
+                rsa::Pkcs1v15Sign::new::<sha2::Sha384>(),
+                &sha2::Sha384::digest(message),
+            ),
+            SignatureScheme::RSA_PKCS1_SHA256 => self.key.sign_with_rng(
+                rng,
+                rsa::Pkcs1v15Sign::new::<sha2::Sha256>(),
+                &sha2::Sha256::digest(message),
+            ),
+            _ => unreachable!("`RsaSigningKey::choose_scheme` only picks from `RSA_SCHEMES`"),
+        }
+        .map_err(|_| rustls::Error::General("signing failed".into()))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Parses a SEC1 `ECPrivateKey`, rejecting keys that name a different curve.
+///
+/// The curve parameters are optional in SEC1; keys without them are only
+/// checked by the length of the private scalar.
+#[cfg(feature = "ecdsa-sign")]
+fn sec1_key(
+    der: &[u8],
+    curve: pkcs8::ObjectIdentifier,
+) -> Result<sec1::EcPrivateKey<'_>, rustls::Error> {
+    let key = sec1::EcPrivateKey::try_from(der).map_err(key_error)?;
+
+    match key.parameters.and_then(|params| params.named_curve()) {
+        Some(named_curve) if named_curve != curve => Err(rustls::Error::General(
+            "private key is for a different curve".into(),
+        )),
+        _ => Ok(key),
+    }
+}
+
+fn key_error(err: impl core::fmt::Display) -> rustls::Error {
+    rustls::Error::General(alloc::format!("{}", err))
+}
+
+fn unsupported_format() -> rustls::Error {
+    rustls::Error::General("unsupported private key format".into())
+}
@@ -0,0 +1,261 @@
+//! A sans-I/O SNTPv4 client (RFC4330, RFC5905).
+//!
+//! It builds requests and validates replies, and leaves the sockets and the clocks to the
+//! caller, e.g. `embassy-net` on the board or `std::net::UdpSocket` against a local stand-in
+//! server on the host.
+
+use core::time::Duration;
+
+pub const PACKET_LEN: usize = 48;
+pub const PORT: u16 = 123;
+
+/// Unix time of the first of the 32-bit NTP timestamps (1900-01-01).
+const NTP_TO_UNIX: u64 = 2_208_988_800;
+/// Server timestamps are read as the first time at or after 2024-01-01 with the same
+/// 32-bit NTP seconds, so they stay correct past the NTP era rollover of 2036 until 2160.
+const ERA_PIVOT: u64 = 1_704_067_200;
+
+const VERSION: u8 = 4;
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+const LEAP_UNSYNCHRONIZED: u8 = 3;
+const MAX_STRATUM: u8 = 15;
+
+/// One request to a server, kept to validate its reply.
+#[derive(Clone, Debug)]
+pub struct Request {
+    nonce: [u8; 8],
+    sent: u64,
+}
+
+impl Request {
+    /// A request sent when the monotonic clock reads `sent` microseconds.
+    ///
+    /// `nonce` goes in the transmit timestamp instead of the (unknown) client time, and the
+    /// server echoes it in the origin timestamp of its reply: use random bytes, so that
+    /// off-path attackers can't forge replies (RFC9109).
+    pub fn new(nonce: [u8; 8], sent: u64) -> Self {
+        Self { nonce, sent }
+    }
+
+    pub fn packet(&self) -> [u8; PACKET_LEN] {
+        let mut packet = [0; PACKET_LEN];
+        packet[0] = (VERSION << 3) | MODE_CLIENT;
+        packet[40..48].copy_from_slice(&self.nonce);
+        packet
+    }
+
+    /// Validates `reply`, received when the monotonic clock read `received` microseconds.
+    ///
+    /// [`Error::OriginMismatch`] means the reply belongs to another request (or was forged)
+    /// and should be ignored; [`Error::KissOfDeath`] means the server must not be asked again
+    /// for a while.
+    pub fn reply(&self, reply: &[u8], received: u64) -> Result<Sample, Error> {
+        let reply: &[u8; PACKET_LEN] = reply
+            .get(..PACKET_LEN)
+            .and_then(|reply| reply.try_into().ok())
+            .ok_or(Error::Truncated)?;
+
+        let leap = reply[0] >> 6;
+        let version = (reply[0] >> 3) & 0x07;
+        let mode = reply[0] & 0x07;
+        let stratum = reply[1];
+        if mode != MODE_SERVER || !(3..=VERSION).contains(&version) {
+            return Err(Error::NotServerReply);
+        }
+        if reply[24..32] != self.nonce {
+            return Err(Error::OriginMismatch);
+        }
+        if stratum == 0 {
+            return Err(Error::KissOfDeath(reply[12..16].try_into().unwrap()));
+        }
+        if leap == LEAP_UNSYNCHRONIZED || stratum > MAX_STRATUM {
+            return Err(Error::Unsynchronized);
+        }
+
+        let server_received = timestamp(reply, 32);
+        let server_sent = timestamp(reply, 40);
+        // the difference wraps with the timestamps at the end of an era
+        let held = server_sent.wrapping_sub(server_received);
+        if server_received == 0 || server_sent == 0 || held >> 63 == 1 {
+            return Err(Error::InvalidTimestamps);
+        }
+
+        // RFC5905 section 8: the round trip minus the time the server held the request
+        let round_trip = Duration::from_micros(received.saturating_sub(self.sent));
+        let held = Duration::from_secs(held >> 32) + fraction(held);
+        let delay = round_trip.saturating_sub(held);
+
+        Ok(Sample {
+            unix: unix_time(server_sent) + delay / 2,
+            at: received,
+            delay,
+            stratum,
+        })
+    }
+}
+
+/// The time from a valid reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// The Unix time when the reply was received, i.e. the server time plus half the delay.
+    pub unix: Duration,
+    /// The monotonic clock reading passed to [`Request::reply`].
+    pub at: u64,
+    /// The round trip delay; `unix` is off by at most half of it.
+    pub delay: Duration,
+    pub stratum: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    Truncated,
+    /// Not an NTPv3 or NTPv4 server-mode packet.
+    NotServerReply,
+    /// The origin timestamp isn't the transmit timestamp of the request.
+    OriginMismatch,
+    /// A Kiss-o'-Death packet with its ASCII code, e.g. `RATE` or `DENY`.
+    KissOfDeath([u8; 4]),
+    /// The server's clock isn't synchronized (leap indicator 3 or stratum 16).
+    Unsynchronized,
+    InvalidTimestamps,
+}
+
+/// The 64-bit timestamp at `offset`: seconds since the start of the NTP era in the upper
+/// 32 bits, and the fraction of a second in the lower ones.
+fn timestamp(packet: &[u8; PACKET_LEN], offset: usize) -> u64 {
+    u64::from_be_bytes(packet[offset..offset + 8].try_into().unwrap())
+}
+
+fn fraction(timestamp: u64) -> Duration {
+    Duration::from_nanos(((timestamp & 0xffff_ffff) * 1_000_000_000) >> 32)
+}
+
+fn unix_time(timestamp: u64) -> Duration {
+    let since_pivot = ((timestamp >> 32) as u32).wrapping_sub((ERA_PIVOT + NTP_TO_UNIX) as u32);
+    Duration::from_secs(ERA_PIVOT + u64::from(since_pivot)) + fraction(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONCE: [u8; 8] = *b"\x01\x23\x45\x67\x89\xab\xcd\xef";
+    /// 2024-06-01T00:00:00Z in NTP seconds.
+    const JUNE_2024: u64 = 1_717_200_000 + NTP_TO_UNIX;
+
+    /// A stratum 2 reply to a request with [`NONCE`], received at `t2` and sent at `t3`.
+    fn reply(t2: u64, t3: u64) -> [u8; PACKET_LEN] {
+        let mut reply = [0; PACKET_LEN];
+        reply[0] = (VERSION << 3) | MODE_SERVER;
+        reply[1] = 2;
+        reply[24..32].copy_from_slice(&NONCE);
+        reply[32..40].copy_from_slice(&t2.to_be_bytes());
+        reply[40..48].copy_from_slice(&t3.to_be_bytes());
+        reply
+    }
+
+    #[test]
+    fn request() {
+        let packet = Request::new(NONCE, 0).packet();
+        assert_eq!(packet[0], 0x23);
+        assert_eq!(packet[40..48], NONCE);
+        assert!(packet[1..40].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn offset_and_delay() {
+        // held for 0.25s by the server, and received a second after it was sent
+        let t2 = JUNE_2024 << 32;
+        let t3 = t2 + (1 << 30);
+        let sample = Request::new(NONCE, 5_000_000)
+            .reply(&reply(t2, t3), 6_000_000)
+            .unwrap();
+        assert_eq!(
+            sample,
+            Sample {
+                unix: Duration::from_secs(1_717_200_000) + Duration::from_millis(250 + 375),
+                at: 6_000_000,
+                delay: Duration::from_millis(750),
+                stratum: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn era_rollover() {
+        // 2036-02-07T06:28:16Z is NTP second 0 again
+        let rollover = 2_085_978_496;
+        let t2 = 0xffff_ffff_8000_0000;
+        let t3 = 1 << 32;
+        let sample = Request::new(NONCE, 0).reply(&reply(t2, t3), 0).unwrap();
+        assert_eq!(sample.unix, Duration::from_secs(rollover + 1));
+        assert_eq!(sample.delay, Duration::ZERO);
+    }
+
+    #[test]
+    fn origin_mismatch() {
+        let request = Request::new(*b"another!", 0);
+        let reply = reply(JUNE_2024 << 32, JUNE_2024 << 32);
+        assert_eq!(request.reply(&reply, 0), Err(Error::OriginMismatch));
+    }
+
+    #[test]
+    fn kiss_of_death() {
+        let mut reply = reply(0, 0);
+        reply[1] = 0;
+        reply[12..16].copy_from_slice(b"RATE");
+        let request = Request::new(NONCE, 0);
+        assert_eq!(request.reply(&reply, 0), Err(Error::KissOfDeath(*b"RATE")));
+        // only from the server that was asked
+        reply[24] ^= 1;
+        assert_eq!(request.reply(&reply, 0), Err(Error::OriginMismatch));
+    }
+
+    #[test]
+    fn unsynchronized() {
+        let request = Request::new(NONCE, 0);
+        let mut reply = reply(JUNE_2024 << 32, JUNE_2024 << 32);
+        reply[1] = 16;
+        assert_eq!(request.reply(&reply, 0), Err(Error::Unsynchronized));
+        reply[1] = 2;
+        reply[0] |= LEAP_UNSYNCHRONIZED << 6;
+        assert_eq!(request.reply(&reply, 0), Err(Error::Unsynchronized));
+    }
+
+    #[test]
+    fn not_a_server_reply() {
+        let request = Request::new(NONCE, 0);
+        let valid = reply(JUNE_2024 << 32, JUNE_2024 << 32);
+        for first in [
+            (VERSION << 3) | MODE_CLIENT,
+            (VERSION << 3) | 5,
+            (2 << 3) | MODE_SERVER,
+            (5 << 3) | MODE_SERVER,
+        ] {
+            let mut reply = valid;
+            reply[0] = first;
+            assert_eq!(request.reply(&reply, 0), Err(Error::NotServerReply));
+        }
+        // NTPv3 servers are fine
+        let mut reply = valid;
+        reply[0] = (3 << 3) | MODE_SERVER;
+        assert!(request.reply(&reply, 0).is_ok());
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        let request = Request::new(NONCE, 0);
+        let t = JUNE_2024 << 32;
+        for (t2, t3) in [(0, t), (t, 0), (t + 1, t)] {
+            assert_eq!(
+                request.reply(&reply(t2, t3), 0),
+                Err(Error::InvalidTimestamps)
+            );
+        }
+        assert_eq!(
+            request.reply(&reply(t, t)[..PACKET_LEN - 1], 0),
+            Err(Error::Truncated)
+        );
+    }
+}
@@ -1,24 +1,260 @@
+#[cfg(feature = "rsa-verify")]
 use der::Reader;
-use rsa::signature::Verifier;
+#[cfg(feature = "ecdsa-verify")]
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+#[cfg(feature = "rsa-verify")]
 use rsa::{pkcs1v15, pss, BigUint, RsaPublicKey};
 use rustls::pki_types::{AlgorithmIdentifier, InvalidSignature, SignatureVerificationAlgorithm};
 use rustls::{crypto::WebPkiSupportedAlgorithms, SignatureScheme};
+#[cfg(feature = "ecdsa-verify")]
+use sha2::Digest;
+#[cfg(any(feature = "ecdsa-verify", feature = "rsa-verify"))]
+use signature::Verifier;
 use webpki::alg_id;
 
 pub static ALGORITHMS: WebPkiSupportedAlgorithms = WebPkiSupportedAlgorithms {
-    all: &[RSA_PSS_SHA256, RSA_PKCS1_SHA256],
+    all: &[
+        #[cfg(feature = "ecdsa-verify")]
+        ECDSA_P256_SHA256,
+        #[cfg(feature = "ecdsa-verify")]
+        ECDSA_P256_SHA384,
+        #[cfg(feature = "ecdsa-verify")]
+        ECDSA_P384_SHA256,
+        #[cfg(feature = "ecdsa-verify")]
+        ECDSA_P384_SHA384,
+        #[cfg(feature = "ed25519-verify")]
+        ED25519,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PSS_SHA256,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PSS_SHA384,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PSS_SHA512,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PKCS1_SHA256,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PKCS1_SHA384,
+        #[cfg(feature = "rsa-verify")]
+        RSA_PKCS1_SHA512,
+    ],
     mapping: &[
+        // TLS1.2 doesn't tie the ECDSA hash to the curve of the key
+        #[cfg(feature = "ecdsa-verify")]
+        (
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            &[ECDSA_P384_SHA384, ECDSA_P256_SHA384],
+        ),
+        #[cfg(feature = "ecdsa-verify")]
+        (
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            &[ECDSA_P256_SHA256, ECDSA_P384_SHA256],
+        ),
+        #[cfg(feature = "ed25519-verify")]
+        (SignatureScheme::ED25519, &[ED25519]),
+        #[cfg(feature = "rsa-verify")]
+        (SignatureScheme::RSA_PSS_SHA512, &[RSA_PSS_SHA512]),
+        #[cfg(feature = "rsa-verify")]
+        (SignatureScheme::RSA_PSS_SHA384, &[RSA_PSS_SHA384]),
+        #[cfg(feature = "rsa-verify")]
         (SignatureScheme::RSA_PSS_SHA256, &[RSA_PSS_SHA256]),
+        #[cfg(feature = "rsa-verify")]
+        (SignatureScheme::RSA_PKCS1_SHA512, &[RSA_PKCS1_SHA512]),
+        #[cfg(feature = "rsa-verify")]
+        (SignatureScheme::RSA_PKCS1_SHA384, &[RSA_PKCS1_SHA384]),
+        #[cfg(feature = "rsa-verify")]
         (SignatureScheme::RSA_PKCS1_SHA256, &[RSA_PKCS1_SHA256]),
     ],
 };
 
+#[cfg(feature = "ecdsa-verify")]
+static ECDSA_P256_SHA256: &dyn SignatureVerificationAlgorithm = &EcdsaP256Sha256Verify;
+#[cfg(feature = "ecdsa-verify")]
+static ECDSA_P256_SHA384: &dyn SignatureVerificationAlgorithm = &EcdsaP256Sha384Verify;
+#[cfg(feature = "ecdsa-verify")]
+static ECDSA_P384_SHA256: &dyn SignatureVerificationAlgorithm = &EcdsaP384Sha256Verify;
+#[cfg(feature = "ecdsa-verify")]
+static ECDSA_P384_SHA384: &dyn SignatureVerificationAlgorithm = &EcdsaP384Sha384Verify;
+#[cfg(feature = "ed25519-verify")]
+static ED25519: &dyn SignatureVerificationAlgorithm = &Ed25519Verify;
+#[cfg(feature = "rsa-verify")]
 static RSA_PSS_SHA256: &dyn SignatureVerificationAlgorithm = &RsaPssSha256Verify;
+#[cfg(feature = "rsa-verify")]
+static RSA_PSS_SHA384: &dyn SignatureVerificationAlgorithm = &RsaPssSha384Verify;
+#[cfg(feature = "rsa-verify")]
+static RSA_PSS_SHA512: &dyn SignatureVerificationAlgorithm = &RsaPssSha512Verify;
+#[cfg(feature = "rsa-verify")]
 static RSA_PKCS1_SHA256: &dyn SignatureVerificationAlgorithm = &RsaPkcs1Sha256Verify;
+#[cfg(feature = "rsa-verify")]
+static RSA_PKCS1_SHA384: &dyn SignatureVerificationAlgorithm = &RsaPkcs1Sha384Verify;
+#[cfg(feature = "rsa-verify")]
+static RSA_PKCS1_SHA512: &dyn SignatureVerificationAlgorithm = &RsaPkcs1Sha512Verify;
+
+#[cfg(feature = "ecdsa-verify")]
+#[derive(Debug)]
+struct EcdsaP256Sha256Verify;
+
+#[cfg(feature = "ecdsa-verify")]
+impl SignatureVerificationAlgorithm for EcdsaP256Sha256Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_P256
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_SHA256
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key =
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key).map_err(|_| InvalidSignature)?;
+
+        let signature =
+            p256::ecdsa::DerSignature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        public_key
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "ecdsa-verify")]
+#[derive(Debug)]
+struct EcdsaP256Sha384Verify;
+
+#[cfg(feature = "ecdsa-verify")]
+impl SignatureVerificationAlgorithm for EcdsaP256Sha384Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_P256
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_SHA384
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key =
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key).map_err(|_| InvalidSignature)?;
+
+        let signature =
+            p256::ecdsa::Signature::from_der(signature).map_err(|_| InvalidSignature)?;
+
+        public_key
+            .verify_prehash(&sha2::Sha384::digest(message), &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "ecdsa-verify")]
+#[derive(Debug)]
+struct EcdsaP384Sha256Verify;
+
+#[cfg(feature = "ecdsa-verify")]
+impl SignatureVerificationAlgorithm for EcdsaP384Sha256Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_P384
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_SHA256
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key =
+            p384::ecdsa::VerifyingKey::from_sec1_bytes(public_key).map_err(|_| InvalidSignature)?;
+
+        let signature =
+            p384::ecdsa::Signature::from_der(signature).map_err(|_| InvalidSignature)?;
+
+        public_key
+            .verify_prehash(&sha2::Sha256::digest(message), &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "ecdsa-verify")]
+#[derive(Debug)]
+struct EcdsaP384Sha384Verify;
+
+#[cfg(feature = "ecdsa-verify")]
+impl SignatureVerificationAlgorithm for EcdsaP384Sha384Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_P384
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ECDSA_SHA384
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key =
+            p384::ecdsa::VerifyingKey::from_sec1_bytes(public_key).map_err(|_| InvalidSignature)?;
+
+        let signature =
+            p384::ecdsa::DerSignature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        public_key
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "ed25519-verify")]
+#[derive(Debug)]
+struct Ed25519Verify;
+
+#[cfg(feature = "ed25519-verify")]
+impl SignatureVerificationAlgorithm for Ed25519Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ED25519
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::ED25519
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key = public_key.try_into().map_err(|_| InvalidSignature)?;
+        let public_key =
+            ed25519_dalek::VerifyingKey::from_bytes(public_key).map_err(|_| InvalidSignature)?;
+
+        let signature =
+            ed25519_dalek::Signature::from_slice(signature).map_err(|_| InvalidSignature)?;
+
+        public_key
+            .verify_strict(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
 
+#[cfg(feature = "rsa-verify")]
 #[derive(Debug)]
 struct RsaPssSha256Verify;
 
+#[cfg(feature = "rsa-verify")]
 impl SignatureVerificationAlgorithm for RsaPssSha256Verify {
     fn public_key_alg_id(&self) -> AlgorithmIdentifier {
         alg_id::RSA_ENCRYPTION
@@ -44,9 +280,71 @@ impl SignatureVerificationAlgorithm for RsaPssSha256Verify {
     }
 }
 
+#[cfg(feature = "rsa-verify")]
+#[derive(Debug)]
+struct RsaPssSha384Verify;
+
+#[cfg(feature = "rsa-verify")]
+impl SignatureVerificationAlgorithm for RsaPssSha384Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_ENCRYPTION
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_PSS_SHA384
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key = decode_spki_spk(public_key)?;
+
+        let signature = pss::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        pss::VerifyingKey::<sha2::Sha384>::new(public_key)
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
+#[derive(Debug)]
+struct RsaPssSha512Verify;
+
+#[cfg(feature = "rsa-verify")]
+impl SignatureVerificationAlgorithm for RsaPssSha512Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_ENCRYPTION
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_PSS_SHA512
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key = decode_spki_spk(public_key)?;
+
+        let signature = pss::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        pss::VerifyingKey::<sha2::Sha512>::new(public_key)
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
 #[derive(Debug)]
 struct RsaPkcs1Sha256Verify;
 
+#[cfg(feature = "rsa-verify")]
 impl SignatureVerificationAlgorithm for RsaPkcs1Sha256Verify {
     fn public_key_alg_id(&self) -> AlgorithmIdentifier {
         alg_id::RSA_ENCRYPTION
@@ -72,15 +370,89 @@ impl SignatureVerificationAlgorithm for RsaPkcs1Sha256Verify {
     }
 }
 
+#[cfg(feature = "rsa-verify")]
+#[derive(Debug)]
+struct RsaPkcs1Sha384Verify;
+
+#[cfg(feature = "rsa-verify")]
+impl SignatureVerificationAlgorithm for RsaPkcs1Sha384Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_ENCRYPTION
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_PKCS1_SHA384
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key = decode_spki_spk(public_key)?;
+
+        let signature = pkcs1v15::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        pkcs1v15::VerifyingKey::<sha2::Sha384>::new(public_key)
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
+#[derive(Debug)]
+struct RsaPkcs1Sha512Verify;
+
+#[cfg(feature = "rsa-verify")]
+impl SignatureVerificationAlgorithm for RsaPkcs1Sha512Verify {
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_ENCRYPTION
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_PKCS1_SHA512
+    }
+
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        let public_key = decode_spki_spk(public_key)?;
+
+        let signature = pkcs1v15::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+
+        pkcs1v15::VerifyingKey::<sha2::Sha512>::new(public_key)
+            .verify(message, &signature)
+            .map_err(|_| InvalidSignature)
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
 fn decode_spki_spk(spki_spk: &[u8]) -> Result<RsaPublicKey, InvalidSignature> {
     // public_key: unfortunately this is not a whole SPKI, but just the key material.
     // decode the two integers manually.
     let mut reader = der::SliceReader::new(spki_spk).map_err(|_| InvalidSignature)?;
     let ne: [der::asn1::UintRef; 2] = reader.decode().map_err(|_| InvalidSignature)?;
 
-    RsaPublicKey::new(
-        BigUint::from_bytes_be(ne[0].as_bytes()),
+    let n = BigUint::from_bytes_be(ne[0].as_bytes());
+    if n.bits() < MIN_RSA_MODULUS_BITS {
+        return Err(InvalidSignature);
+    }
+
+    RsaPublicKey::new_with_max_size(
+        n,
         BigUint::from_bytes_be(ne[1].as_bytes()),
+        MAX_RSA_MODULUS_BITS,
     )
     .map_err(|_| InvalidSignature)
 }
+
+/// Keys outside this range are rejected, matching the policy of the webpki `ring` and
+/// `aws-lc-rs` backends (2048..=8192 bits).
+#[cfg(feature = "rsa-verify")]
+const MIN_RSA_MODULUS_BITS: usize = 2048;
+#[cfg(feature = "rsa-verify")]
+const MAX_RSA_MODULUS_BITS: usize = 8192;
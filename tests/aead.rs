@@ -0,0 +1,158 @@
+#![cfg(all(feature = "std", any(feature = "chacha", feature = "aes-gcm")))]
+
+use rustls::crypto::cipher::{
+    AeadKey, InboundOpaqueMessage, Iv, MessageDecrypter, MessageEncrypter, OutboundPlainMessage,
+};
+use rustls::crypto::ring;
+use rustls::{ContentType, ProtocolVersion, SupportedCipherSuite};
+use wycheproof::aead::{TestName, TestSet};
+use wycheproof::TestResult;
+
+fn key(bytes: &[u8]) -> AeadKey {
+    AeadKey::from(<[u8; 32]>::try_from(bytes).unwrap())
+}
+
+fn iv(bytes: &[u8]) -> Iv {
+    Iv::from(<[u8; 12]>::try_from(bytes).unwrap())
+}
+
+/// Protects `payload` as the first application data record.
+fn seal(encrypter: &mut dyn MessageEncrypter, payload: &[u8]) -> Vec<u8> {
+    let message = OutboundPlainMessage {
+        typ: ContentType::ApplicationData,
+        version: ProtocolVersion::TLSv1_2,
+        payload: payload.into(),
+    };
+    encrypter.encrypt(message, 0).unwrap().encode()
+}
+
+fn open(decrypter: &mut dyn MessageDecrypter, record: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+    let mut payload = record[5..].to_vec();
+    let message = InboundOpaqueMessage::new(
+        ContentType::ApplicationData,
+        ProtocolVersion::TLSv1_2,
+        &mut payload,
+    );
+    decrypter
+        .decrypt(message, 0)
+        .map(|message| message.payload.to_vec())
+}
+
+/// QUIC packet protection is the only rustls interface that takes any nonce and AAD, so the
+/// Wycheproof vectors go through that; packet number 0 leaves the IV as the nonce.
+#[cfg(feature = "chacha")]
+#[test]
+fn chacha20_poly1305_wycheproof() {
+    let quic = no_std_embedded_demo::TLS13_CHACHA20_POLY1305_SHA256
+        .tls13()
+        .unwrap()
+        .quic
+        .unwrap();
+    let set = TestSet::load(TestName::ChaCha20Poly1305).unwrap();
+    for group in set
+        .test_groups
+        .iter()
+        .filter(|group| group.nonce_size == 96)
+    {
+        for test in &group.tests {
+            let packet_key = quic.packet_key(key(&test.key), iv(&test.nonce));
+            let mut sealed = test.ct.to_vec();
+            sealed.extend_from_slice(&test.tag);
+            let opened = packet_key
+                .decrypt_in_place(0, &test.aad, &mut sealed.clone())
+                .map(|plaintext| plaintext.to_vec());
+            if test.result == TestResult::Invalid {
+                assert!(opened.is_err(), "test {}: {}", test.tc_id, test.comment);
+                continue;
+            }
+            assert_eq!(opened.unwrap(), &test.pt[..], "test {}", test.tc_id);
+
+            let mut payload = test.pt.to_vec();
+            let tag = packet_key
+                .encrypt_in_place(0, &test.aad, &mut payload)
+                .unwrap();
+            payload.extend_from_slice(tag.as_ref());
+            assert_eq!(payload, sealed, "test {}", test.tc_id);
+
+            for len in 0..sealed.len() {
+                assert!(packet_key
+                    .decrypt_in_place(0, &test.aad, &mut sealed[..len].to_vec())
+                    .is_err());
+            }
+        }
+    }
+}
+
+/// Seals the plaintexts of the Wycheproof vectors as TLS1.3 records with the vector's key and
+/// nonce, and compares them with *ring*'s records for the same suite.
+fn tls13_wycheproof(name: TestName, ours: SupportedCipherSuite, theirs: SupportedCipherSuite) {
+    let (ours, theirs) = (ours.tls13().unwrap(), theirs.tls13().unwrap());
+    let set = TestSet::load(name).unwrap();
+    for group in &set.test_groups {
+        if group.nonce_size != 96 || group.key_size != 8 * ours.aead_alg.key_len() {
+            continue;
+        }
+        for test in group
+            .tests
+            .iter()
+            .filter(|test| test.result == TestResult::Valid)
+        {
+            let record = seal(
+                &mut *ours.aead_alg.encrypter(key(&test.key), iv(&test.nonce)),
+                &test.pt,
+            );
+            let expected = seal(
+                &mut *theirs.aead_alg.encrypter(key(&test.key), iv(&test.nonce)),
+                &test.pt,
+            );
+            assert_eq!(record, expected, "test {}", test.tc_id);
+
+            let mut decrypter = ours.aead_alg.decrypter(key(&test.key), iv(&test.nonce));
+            assert_eq!(
+                open(&mut *decrypter, &record).unwrap(),
+                &test.pt[..],
+                "test {}",
+                test.tc_id
+            );
+            for i in 5..record.len() {
+                let mut tampered = record.clone();
+                tampered[i] ^= 0x80;
+                assert_eq!(
+                    open(&mut *decrypter, &tampered),
+                    Err(rustls::Error::DecryptError),
+                    "test {}, byte {i}",
+                    test.tc_id
+                );
+            }
+            for len in 5..record.len() {
+                assert_eq!(
+                    open(&mut *decrypter, &record[..len]),
+                    Err(rustls::Error::DecryptError),
+                    "test {}, {len} bytes",
+                    test.tc_id
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "chacha")]
+#[test]
+fn tls13_chacha20_poly1305_wycheproof() {
+    tls13_wycheproof(
+        TestName::ChaCha20Poly1305,
+        no_std_embedded_demo::TLS13_CHACHA20_POLY1305_SHA256,
+        ring::cipher_suite::TLS13_CHACHA20_POLY1305_SHA256,
+    );
+}
+
+// `AeadKey` can only be built from 32 bytes, so AES-128 is left to the handshakes in records.rs
+#[cfg(feature = "aes-gcm")]
+#[test]
+fn tls13_aes_256_gcm_wycheproof() {
+    tls13_wycheproof(
+        TestName::AesGcm,
+        no_std_embedded_demo::TLS13_AES_256_GCM_SHA384,
+        ring::cipher_suite::TLS13_AES_256_GCM_SHA384,
+    );
+}
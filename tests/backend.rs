@@ -0,0 +1,64 @@
+#![cfg(all(
+    feature = "std",
+    feature = "ecdsa-sign",
+    feature = "ecdsa-verify",
+    any(feature = "chacha", feature = "aes-gcm")
+))]
+
+mod common;
+
+use common::{Identity, Peers, TLS13};
+use no_std_embedded_demo::backend::{self, Call, Recording};
+use no_std_embedded_demo::ProviderBuilder;
+use rustls::CipherSuite;
+
+#[cfg(feature = "chacha")]
+const SUITE: (CipherSuite, Call) = (
+    CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+    Call::ChaCha20Poly1305,
+);
+#[cfg(not(feature = "chacha"))]
+const SUITE: (CipherSuite, Call) = (
+    CipherSuite::TLS13_AES_128_GCM_SHA256,
+    Call::AesGcm { key_len: 16 },
+);
+
+/// The backend is global, so this is the only test in its binary.
+#[test]
+fn handshake_goes_through_the_backend() {
+    let (suite, key_call) = SUITE;
+    let recording: &'static Recording = Box::leak(Box::default());
+    backend::set_backend(recording).unwrap();
+    assert!(backend::set_backend(&backend::Software).is_err());
+
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let provider = || {
+        ProviderBuilder::new()
+            .with_cipher_suites(&[suite])
+            .build()
+            .unwrap()
+    };
+    let mut peers = Peers::connect(
+        identity.client_config(provider(), TLS13),
+        identity.server_config(provider(), TLS13),
+    )
+    .unwrap();
+    let calls = recording.take();
+    for call in [Call::Sha256, Call::HmacSha256, key_call] {
+        assert!(calls.contains(&call), "{call:?} not in {calls:?}");
+    }
+    // both sides' handshake traffic keys, each way
+    assert!(calls.iter().filter(|call| **call == key_call).count() >= 4);
+
+    peers.round_trip(b"hello");
+    // the payload, its content type byte, then the same back
+    assert_eq!(
+        recording.take(),
+        [
+            Call::Seal { len: 6 },
+            Call::Open { len: 6 },
+            Call::Seal { len: 6 },
+            Call::Open { len: 6 },
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+#![cfg(all(feature = "std", feature = "all-algorithms"))]
+
+use no_std_embedded_demo::{provider, ProviderBuilder, SignatureSchemes};
+use rustls::crypto::CryptoProvider;
+use rustls::{CipherSuite, SignatureScheme};
+
+fn build(schemes: &'static SignatureSchemes) -> Result<CryptoProvider, rustls::Error> {
+    ProviderBuilder::new()
+        .with_cipher_suites(&[CipherSuite::TLS13_CHACHA20_POLY1305_SHA256])
+        .with_signature_schemes(schemes)
+        .build()
+}
+
+/// Each list keeps its own mapping, however many providers and lists are built.
+#[test]
+fn signature_scheme_lists_are_kept() {
+    let schemes = [
+        SignatureScheme::ECDSA_NISTP256_SHA256,
+        SignatureScheme::ED25519,
+        SignatureScheme::RSA_PSS_SHA256,
+    ];
+    let mut lists: Vec<Vec<SignatureScheme>> = schemes.iter().map(|s| vec![*s]).collect();
+    for first in schemes {
+        for second in schemes.into_iter().filter(|s| *s != first) {
+            lists.push(vec![first, second]);
+        }
+    }
+    // more than the 8 lists the builder used to be limited to
+    for list in lists.iter().cycle().take(20) {
+        let schemes: &'static SignatureSchemes =
+            Box::leak(Box::new(SignatureSchemes::new(list.clone().leak())));
+        let mapping = build(schemes)
+            .unwrap()
+            .signature_verification_algorithms
+            .mapping;
+        let again = build(schemes)
+            .unwrap()
+            .signature_verification_algorithms
+            .mapping;
+        assert!(core::ptr::eq(mapping, again));
+        assert_eq!(mapping.iter().map(|m| m.0).collect::<Vec<_>>(), *list);
+    }
+
+    static UNKNOWN: SignatureSchemes = SignatureSchemes::new(&[SignatureScheme::Unknown(0x0999)]);
+    static TWICE: SignatureSchemes =
+        SignatureSchemes::new(&[SignatureScheme::ED25519, SignatureScheme::ED25519]);
+    assert!(build(&UNKNOWN).is_err());
+    assert!(build(&TWICE).is_err());
+
+    // without a list, the provider keeps the mapping of the build
+    let all = ProviderBuilder::new().build().unwrap();
+    assert!(core::ptr::eq(
+        all.signature_verification_algorithms.mapping,
+        provider().signature_verification_algorithms.mapping
+    ));
+}
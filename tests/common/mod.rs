@@ -0,0 +1,145 @@
+//! In-memory handshakes between two rustls connections, shared by the tests.
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{
+    ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection,
+    SupportedProtocolVersion,
+};
+
+pub const TLS13: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+#[cfg(feature = "tls12")]
+pub const TLS12: &[&SupportedProtocolVersion] = &[&rustls::version::TLS12];
+
+/// A self-signed certificate for `localhost` and its private key.
+pub struct Identity {
+    pub cert: CertificateDer<'static>,
+    pub key: PrivateKeyDer<'static>,
+}
+
+impl Identity {
+    pub fn generate(algorithm: &'static rcgen::SignatureAlgorithm) -> Self {
+        let key = rcgen::KeyPair::generate_for(algorithm).unwrap();
+        Self::sign(key)
+    }
+
+    /// ring can't generate RSA keys.
+    #[cfg(any(feature = "rsa-sign", feature = "rsa-verify"))]
+    pub fn rsa() -> Self {
+        use rsa::pkcs8::EncodePrivateKey;
+
+        let key = rsa::RsaPrivateKey::new(&mut rand_core::OsRng, 2048).unwrap();
+        let der = PrivatePkcs8KeyDer::from(key.to_pkcs8_der().unwrap().as_bytes().to_vec());
+        Self::sign(
+            rcgen::KeyPair::from_pkcs8_der_and_sign_algo(&der, &rcgen::PKCS_RSA_SHA256).unwrap(),
+        )
+    }
+
+    fn sign(key: rcgen::KeyPair) -> Self {
+        let params = rcgen::CertificateParams::new(vec!["localhost".into()]).unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        Self {
+            cert: cert.der().clone(),
+            key: PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+        }
+    }
+
+    /// Trusts only the certificate.
+    pub fn roots(&self) -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.cert.clone()).unwrap();
+        roots
+    }
+
+    pub fn client_config(
+        &self,
+        provider: CryptoProvider,
+        versions: &[&'static SupportedProtocolVersion],
+    ) -> ClientConfig {
+        ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(versions)
+            .unwrap()
+            .with_root_certificates(self.roots())
+            .with_no_client_auth()
+    }
+
+    pub fn server_config(
+        &self,
+        provider: CryptoProvider,
+        versions: &[&'static SupportedProtocolVersion],
+    ) -> ServerConfig {
+        ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(versions)
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![self.cert.clone()], self.key.clone_key())
+            .unwrap()
+    }
+}
+
+pub struct Peers {
+    pub client: Connection,
+    pub server: Connection,
+}
+
+impl Peers {
+    /// Runs the handshake between a client and a server with these configs.
+    pub fn connect(client: ClientConfig, server: ServerConfig) -> Result<Self, rustls::Error> {
+        let mut peers = Self {
+            client: ClientConnection::new(Arc::new(client), "localhost".try_into().unwrap())?
+                .into(),
+            server: ServerConnection::new(Arc::new(server))?.into(),
+        };
+        while peers.client.is_handshaking() || peers.server.is_handshaking() {
+            let sent = transfer(&mut peers.client, &mut peers.server)?;
+            if !(transfer(&mut peers.server, &mut peers.client)? || sent) {
+                return Err(rustls::Error::General("the handshake stalled".into()));
+            }
+        }
+        Ok(peers)
+    }
+
+    /// Sends `data` from the client to the server and back.
+    pub fn round_trip(&mut self, data: &[u8]) {
+        let received = send(&mut self.client, &mut self.server, data);
+        assert_eq!(received, data);
+        let received = send(&mut self.server, &mut self.client, &received);
+        assert_eq!(received, data);
+    }
+}
+
+/// Moves the pending records of `from` to `to`; false if there were none.
+fn transfer(from: &mut Connection, to: &mut Connection) -> Result<bool, rustls::Error> {
+    let mut records = Vec::new();
+    while from.wants_write() {
+        from.write_tls(&mut records).unwrap();
+    }
+    let mut pending = &records[..];
+    while !pending.is_empty() {
+        to.read_tls(&mut pending).unwrap();
+    }
+    to.process_new_packets()?;
+    Ok(!records.is_empty())
+}
+
+/// rustls buffers at most 16 KiB of received plaintext, so `data` goes in pieces.
+fn send(from: &mut Connection, to: &mut Connection, data: &[u8]) -> Vec<u8> {
+    let mut received = vec![0; data.len()];
+    for (piece, received) in data.chunks(8192).zip(received.chunks_mut(8192)) {
+        from.writer().write_all(piece).unwrap();
+        transfer(from, to).unwrap();
+        to.reader().read_exact(received).unwrap();
+    }
+    received
+}
+
+pub fn hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,114 @@
+#![cfg(all(feature = "std", feature = "ecdsa-sign", feature = "ecdsa-verify"))]
+
+mod common;
+
+use common::{Identity, Peers};
+use no_std_embedded_demo::provider;
+use rustls::crypto::{ring, CryptoProvider, SupportedKxGroup};
+use rustls::{Connection, SupportedCipherSuite};
+
+/// `provider` cut down to one suite and one group.
+fn only(
+    provider: &CryptoProvider,
+    suite: SupportedCipherSuite,
+    group: &dyn SupportedKxGroup,
+) -> CryptoProvider {
+    CryptoProvider {
+        cipher_suites: provider
+            .cipher_suites
+            .iter()
+            .copied()
+            .filter(|s| s.suite() == suite.suite())
+            .collect(),
+        kx_groups: provider
+            .kx_groups
+            .iter()
+            .copied()
+            .filter(|g| g.name() == group.name())
+            .collect(),
+        ..provider.clone()
+    }
+}
+
+fn exporter(connection: &Connection) -> [u8; 64] {
+    connection
+        .export_keying_material([0; 64], b"EXPERIMENTAL differential", Some(b"context"))
+        .unwrap()
+}
+
+/// Connects our provider to *ring*'s, one way and then the other, with `suite` and `group`.
+fn differential(suite: SupportedCipherSuite, group: &dyn SupportedKxGroup, identity: &Identity) {
+    let (ours, theirs) = (provider(), ring::default_provider());
+    let (ours, theirs) = (only(&ours, suite, group), only(&theirs, suite, group));
+    let versions = &[suite.version()];
+    let data: Vec<u8> = (0..20_000u32).map(|i| i as u8).collect();
+    for (client, server) in [(&ours, &theirs), (&theirs, &ours)] {
+        let mut peers = Peers::connect(
+            identity.client_config(client.clone(), versions),
+            identity.server_config(server.clone(), versions),
+        )
+        .unwrap_or_else(|e| panic!("{:?} {:?}: {e}", suite.suite(), group.name()));
+        for connection in [&peers.client, &peers.server] {
+            assert_eq!(connection.protocol_version(), Some(suite.version().version));
+            assert_eq!(
+                connection.negotiated_cipher_suite().unwrap().suite(),
+                suite.suite()
+            );
+            assert_eq!(
+                connection.negotiated_key_exchange_group().unwrap().name(),
+                group.name()
+            );
+        }
+        assert_eq!(
+            exporter(&peers.client),
+            exporter(&peers.server),
+            "{:?} {:?}",
+            suite.suite(),
+            group.name()
+        );
+        peers.round_trip(&data);
+        peers.round_trip(b"x");
+    }
+}
+
+/// Every suite and group that *ring* has as well; it has no CCM and no X25519MLKEM768.
+#[test]
+fn against_ring() {
+    let ecdsa = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    #[cfg(all(feature = "rsa-sign", feature = "rsa-verify"))]
+    let rsa = Identity::rsa();
+    let ring = ring::default_provider();
+    let mut tested = 0;
+    for suite in provider().cipher_suites {
+        if !ring
+            .cipher_suites
+            .iter()
+            .any(|s| s.suite() == suite.suite())
+        {
+            continue;
+        }
+        let identity = match suite {
+            #[cfg(feature = "tls12")]
+            SupportedCipherSuite::Tls12(tls12)
+                if !tls12
+                    .sign
+                    .contains(&rustls::SignatureScheme::ECDSA_NISTP256_SHA256) =>
+            {
+                #[cfg(all(feature = "rsa-sign", feature = "rsa-verify"))]
+                {
+                    &rsa
+                }
+                #[cfg(not(all(feature = "rsa-sign", feature = "rsa-verify")))]
+                continue;
+            }
+            _ => &ecdsa,
+        };
+        for group in provider().kx_groups {
+            if ring.kx_groups.iter().any(|g| g.name() == group.name()) {
+                differential(suite, group, identity);
+                tested += 1;
+            }
+        }
+    }
+    assert!(tested > 0);
+}
@@ -0,0 +1,204 @@
+//! rustls 0.23 only implements the client side of ECH, so there's no server to accept it:
+//! these tests open the client's encrypted inner `ClientHello` with the HPKE suite and the
+//! ECH config's private key, then check that a server without ECH makes the client reject
+//! the handshake.
+#![cfg(all(feature = "std", feature = "ech", feature = "ecdsa-verify"))]
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{Identity, TLS13};
+use no_std_embedded_demo::{provider, HPKE_SUITES};
+use rustls::client::{EchConfig, EchMode, EchStatus};
+use rustls::crypto::hpke::EncapsulatedSecret;
+use rustls::pki_types::EchConfigListBytes;
+use rustls::{
+    ClientConfig, ClientConnection, EncryptedClientHelloError, Error, PeerIncompatible,
+    ServerConnection,
+};
+
+const ECH_EXTENSION: u16 = 0xfe0d;
+const SERVER_NAME_EXTENSION: u16 = 0x0000;
+/// The name in the outer `ClientHello`.
+const PUBLIC_NAME: &str = "public.example";
+/// The name that only the inner `ClientHello` carries, which the certificate is for.
+const INNER_NAME: &str = "localhost";
+
+/// draft-ietf-tls-esni-18 section 4: an `ECHConfig` for DHKEM(X25519, HKDF-SHA256) with one
+/// HKDF-SHA256 cipher suite.
+fn ech_config(public_key: &[u8], aead: u16) -> Vec<u8> {
+    let mut contents = vec![7, 0x00, 0x20];
+    contents.extend((public_key.len() as u16).to_be_bytes());
+    contents.extend(public_key);
+    contents.extend([0x00, 0x04, 0x00, 0x01]);
+    contents.extend(aead.to_be_bytes());
+    contents.push(0);
+    contents.push(PUBLIC_NAME.len() as u8);
+    contents.extend(PUBLIC_NAME.as_bytes());
+    contents.extend([0, 0]);
+
+    let mut config = ECH_EXTENSION.to_be_bytes().to_vec();
+    config.extend((contents.len() as u16).to_be_bytes());
+    config.extend(contents);
+    config
+}
+
+/// The `ECHConfigList` with only `config`.
+fn ech_config_list(config: &[u8]) -> EchConfigListBytes<'static> {
+    let mut list = (config.len() as u16).to_be_bytes().to_vec();
+    list.extend(config);
+    EchConfigListBytes::from(list)
+}
+
+/// Reads the length-prefixed fields of a handshake message.
+struct Reader<'a>(&'a [u8], usize);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        self.1 += len;
+        &self.0[self.1 - len..self.1]
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take(1)[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_be_bytes(self.take(2).try_into().unwrap())
+    }
+
+    fn vec8(&mut self) -> &'a [u8] {
+        let len = self.u8();
+        self.take(len.into())
+    }
+
+    fn vec16(&mut self) -> &'a [u8] {
+        let len = self.u16();
+        self.take(len.into())
+    }
+}
+
+/// The extensions of a `ClientHello` body, with where their data starts in it.
+fn extensions(hello: &[u8]) -> Vec<(u16, usize, &[u8])> {
+    let mut reader = Reader(hello, 0);
+    reader.take(2 + 32);
+    reader.vec8();
+    reader.vec16();
+    reader.vec8();
+    let end = reader.u16() as usize + reader.1;
+    let mut extensions = Vec::new();
+    while reader.1 < end {
+        let typ = reader.u16();
+        let data = reader.vec16();
+        extensions.push((typ, reader.1 - data.len(), data));
+    }
+    extensions
+}
+
+fn extension<'a>(extensions: &[(u16, usize, &'a [u8])], typ: u16) -> Option<&'a [u8]> {
+    extensions
+        .iter()
+        .find(|(t, _, _)| *t == typ)
+        .map(|(_, _, data)| *data)
+}
+
+/// The host name of a `server_name` extension.
+fn server_name(data: &[u8]) -> &str {
+    let mut reader = Reader(data, 0);
+    let mut list = Reader(reader.vec16(), 0);
+    assert_eq!(list.u8(), 0);
+    std::str::from_utf8(list.vec16()).unwrap()
+}
+
+#[test]
+fn inner_client_hello_opens_with_the_config_key() {
+    let [suite] = HPKE_SUITES else { panic!() };
+    let (public_key, secret_key) = suite.generate_key_pair().unwrap();
+    let config = ech_config(&public_key.0, 0x0003);
+    let ech = EchConfig::new(ech_config_list(&config), HPKE_SUITES).unwrap();
+
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let client_config = ClientConfig::builder_with_provider(Arc::new(provider()))
+        .with_ech(EchMode::Enable(ech))
+        .unwrap()
+        .with_root_certificates(identity.roots())
+        .with_no_client_auth();
+    let mut client =
+        ClientConnection::new(Arc::new(client_config), INNER_NAME.try_into().unwrap()).unwrap();
+
+    let mut records = Vec::new();
+    client.write_tls(&mut records).unwrap();
+    // one handshake record with the whole ClientHello
+    assert_eq!(records[..1], [0x16]);
+    assert_eq!(records[5], 0x01);
+    let outer = &records[9..];
+
+    let outer_extensions = extensions(outer);
+    let outer_name = extension(&outer_extensions, SERVER_NAME_EXTENSION).unwrap();
+    assert_eq!(server_name(outer_name), PUBLIC_NAME);
+    let (_, at, ech_extension) = *outer_extensions
+        .iter()
+        .find(|(typ, _, _)| *typ == ECH_EXTENSION)
+        .unwrap();
+
+    // ECHClientHello: outer, the cipher suite, the config id, enc and payload
+    let mut reader = Reader(ech_extension, 0);
+    assert_eq!(reader.u8(), 0);
+    assert_eq!((reader.u16(), reader.u16()), (0x0001, 0x0003));
+    assert_eq!(reader.u8(), 7);
+    let enc = EncapsulatedSecret(reader.vec16().to_vec());
+    let payload = reader.vec16();
+
+    // the AAD is the outer ClientHello with a zeroed payload
+    let mut aad = outer.to_vec();
+    let payload_at = at + ech_extension.len() - payload.len();
+    aad[payload_at..payload_at + payload.len()].fill(0);
+    let info = [&b"tls ech\0"[..], &config].concat();
+    let inner = suite.open(&enc, &info, &aad, payload, &secret_key).unwrap();
+
+    let inner_extensions = extensions(&inner);
+    let inner_name = extension(&inner_extensions, SERVER_NAME_EXTENSION).unwrap();
+    assert_eq!(server_name(inner_name), INNER_NAME);
+    assert_eq!(extension(&inner_extensions, ECH_EXTENSION), Some(&[1][..]));
+    assert!(!records
+        .windows(INNER_NAME.len())
+        .any(|window| window == INNER_NAME.as_bytes()));
+
+    // a server without ECH answers the outer ClientHello; rustls 0.23 checks its certificate
+    // against the inner name, not the public one, before it reports the rejection
+    let server_config = identity.server_config(provider(), TLS13);
+    let mut server = ServerConnection::new(Arc::new(server_config)).unwrap();
+    let mut pending = &records[..];
+    while !pending.is_empty() {
+        server.read_tls(&mut pending).unwrap();
+    }
+    server.process_new_packets().unwrap();
+    assert_eq!(server.server_name(), Some(PUBLIC_NAME));
+
+    let mut records = Vec::new();
+    while server.wants_write() {
+        server.write_tls(&mut records).unwrap();
+    }
+    let mut pending = &records[..];
+    while !pending.is_empty() {
+        client.read_tls(&mut pending).unwrap();
+    }
+    assert_eq!(
+        client.process_new_packets().unwrap_err(),
+        Error::PeerIncompatible(PeerIncompatible::ServerRejectedEncryptedClientHello(None))
+    );
+    assert_eq!(client.ech_status(), EchStatus::Rejected);
+}
+
+#[test]
+fn config_without_our_suite() {
+    let [suite] = HPKE_SUITES else { panic!() };
+    let (public_key, _) = suite.generate_key_pair().unwrap();
+    // AES-128-GCM
+    let config = ech_config(&public_key.0, 0x0001);
+    assert_eq!(
+        EchConfig::new(ech_config_list(&config), HPKE_SUITES).unwrap_err(),
+        Error::InvalidEncryptedClientHello(EncryptedClientHelloError::NoCompatibleConfig)
+    );
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::hex;
+use no_std_embedded_demo::provider;
+use rustls::crypto::hash::HashAlgorithm;
+use rustls::crypto::tls13::{Hkdf, OkmBlock};
+use wycheproof::mac::{TestName, TestSet};
+use wycheproof::TestResult;
+
+/// The HKDF of a TLS1.3 suite using `hash`, which signs with our HMAC.
+fn hkdf(hash: HashAlgorithm) -> Option<&'static dyn Hkdf> {
+    provider()
+        .cipher_suites
+        .iter()
+        .filter_map(|suite| suite.tls13())
+        .find(|suite| suite.common.hash_provider.algorithm() == hash)
+        .map(|suite| suite.hkdf_provider)
+}
+
+fn wycheproof(name: TestName, hkdf: &dyn Hkdf) {
+    let set = TestSet::load(name).unwrap();
+    for group in &set.test_groups {
+        // `OkmBlock` holds at most 64 bytes
+        for test in group.tests.iter().filter(|test| test.key.len() <= 64) {
+            let tag = hkdf.hmac_sign(&OkmBlock::new(&test.key), &test.msg);
+            // truncated tags are compared with the start of the full one
+            let matches = tag.as_ref()[..group.tag_size / 8] == test.tag[..];
+            assert_eq!(
+                matches,
+                test.result == TestResult::Valid,
+                "test {}: {}",
+                test.tc_id,
+                test.comment
+            );
+        }
+    }
+}
+
+#[test]
+fn hmac_sha256_wycheproof() {
+    wycheproof(TestName::HmacSha256, hkdf(HashAlgorithm::SHA256).unwrap());
+}
+
+#[test]
+fn hmac_sha384_wycheproof() {
+    // only AES-256-GCM comes with SHA-384
+    if let Some(hkdf) = hkdf(HashAlgorithm::SHA384) {
+        wycheproof(TestName::HmacSha384, hkdf);
+    }
+}
+
+/// RFC4231 test case 2: a key shorter than the block.
+#[test]
+fn hmac_sha256_rfc4231() {
+    let tag = hkdf(HashAlgorithm::SHA256)
+        .unwrap()
+        .hmac_sign(&OkmBlock::new(b"Jefe"), b"what do ya want for nothing?");
+    assert_eq!(
+        tag.as_ref(),
+        hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+    );
+}
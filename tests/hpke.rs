@@ -0,0 +1,93 @@
+#![cfg(all(feature = "std", feature = "ech"))]
+
+mod common;
+
+use common::hex;
+use no_std_embedded_demo::HPKE_SUITES;
+use rustls::crypto::hpke::{EncapsulatedSecret, HpkePrivateKey, HpkePublicKey};
+
+/// RFC9180 A.2.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, ChaCha20Poly1305, base mode.
+const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+const SK_R: &str = "8057991eef8f1f1af18f4a9491d16a1ce333f695d4db8e38da75975c4478e0fb";
+const ENC: &str = "1afa08d3dec047a643885163f1180476fa7ddb54c6a8029ea33f95796bf2ac4a";
+const PT: &str = "4265617574792069732074727574682c20747275746820626561757479";
+/// The first two encryptions of the context, with their AAD.
+const ENCRYPTIONS: [(&str, &str); 2] = [
+    (
+        "436f756e742d30",
+        "1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28",
+    ),
+    (
+        "436f756e742d31",
+        "6b53c051e4199c518de79594e1c4ab18b96f081549d45ce015be002090bb119e85285337cc95ba5f59992dc98c",
+    ),
+];
+
+#[test]
+fn rfc9180_vectors() {
+    let [suite] = HPKE_SUITES else { panic!() };
+    let secret_key = HpkePrivateKey::from(hex(SK_R));
+    let enc = EncapsulatedSecret(hex(ENC));
+
+    let mut opener = suite.setup_opener(&enc, &hex(INFO), &secret_key).unwrap();
+    for (aad, ct) in ENCRYPTIONS {
+        assert_eq!(opener.open(&hex(aad), &hex(ct)).unwrap(), hex(PT));
+    }
+
+    let (aad, ct) = ENCRYPTIONS[0];
+    let plaintext = suite
+        .open(&enc, &hex(INFO), &hex(aad), &hex(ct), &secret_key)
+        .unwrap();
+    assert_eq!(plaintext, hex(PT));
+    // the second message needs the second nonce of the context
+    let (aad, ct) = ENCRYPTIONS[1];
+    assert!(suite
+        .open(&enc, &hex(INFO), &hex(aad), &hex(ct), &secret_key)
+        .is_err());
+}
+
+#[test]
+fn seal_open_round_trip() {
+    let [suite] = HPKE_SUITES else { panic!() };
+    let (public_key, secret_key) = suite.generate_key_pair().unwrap();
+    assert_eq!(public_key.0.len(), 32);
+
+    let (enc, ciphertext) = suite
+        .seal(b"info", b"aad", b"inner ClientHello", &public_key)
+        .unwrap();
+    let opened = suite
+        .open(&enc, b"info", b"aad", &ciphertext, &secret_key)
+        .unwrap();
+    assert_eq!(opened, b"inner ClientHello");
+    assert!(suite
+        .open(&enc, b"other info", b"aad", &ciphertext, &secret_key)
+        .is_err());
+    assert!(suite
+        .open(&enc, b"info", b"other aad", &ciphertext, &secret_key)
+        .is_err());
+
+    let (enc, mut sealer) = suite.setup_sealer(b"info", &public_key).unwrap();
+    let mut opener = suite.setup_opener(&enc, b"info", &secret_key).unwrap();
+    for message in [&b"first"[..], b"second", b""] {
+        let ciphertext = sealer.seal(b"aad", message).unwrap();
+        assert_eq!(opener.open(b"aad", &ciphertext).unwrap(), message);
+    }
+
+    let mut tampered = ciphertext.clone();
+    tampered[0] ^= 1;
+    assert!(suite
+        .open(&enc, b"info", b"aad", &tampered, &secret_key)
+        .is_err());
+}
+
+#[test]
+fn rejects_malformed_keys() {
+    let [suite] = HPKE_SUITES else { panic!() };
+    assert!(suite
+        .seal(b"info", b"aad", b"", &HpkePublicKey(vec![0; 31]))
+        .is_err());
+    let secret_key = HpkePrivateKey::from(vec![0; 33]);
+    assert!(suite
+        .setup_opener(&EncapsulatedSecret(hex(ENC)), b"info", &secret_key)
+        .is_err());
+}
@@ -0,0 +1,103 @@
+#![cfg(all(feature = "std", feature = "mlkem", feature = "ecdsa-sign"))]
+
+mod common;
+
+use common::{Identity, Peers, TLS13};
+use no_std_embedded_demo::{provider, tls13_provider, ProviderBuilder};
+use rustls::crypto::ring;
+use rustls::{CipherSuite, NamedGroup};
+
+const X25519MLKEM768: NamedGroup = NamedGroup::Unknown(0x11ec);
+
+fn group(peers: &Peers) -> NamedGroup {
+    peers.client.negotiated_key_exchange_group().unwrap().name()
+}
+
+#[test]
+fn negotiated_with_tls13() {
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let mut peers = Peers::connect(
+        identity.client_config(tls13_provider(), TLS13),
+        identity.server_config(tls13_provider(), TLS13),
+    )
+    .unwrap();
+    assert_eq!(group(&peers), X25519MLKEM768);
+    peers.round_trip(b"hello");
+}
+
+#[test]
+fn retries_with_x25519_when_the_server_lacks_it() {
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let mut peers = Peers::connect(
+        identity.client_config(tls13_provider(), TLS13),
+        identity.server_config(ring::default_provider(), TLS13),
+    )
+    .unwrap();
+    assert_eq!(group(&peers), NamedGroup::X25519);
+    peers.round_trip(b"hello");
+}
+
+#[cfg(not(feature = "tls12"))]
+#[test]
+fn in_provider_without_tls12() {
+    assert_eq!(provider().kx_groups[0].name(), X25519MLKEM768);
+}
+
+/// A server with `provider()` would otherwise pick the group for TLS1.2 and fail.
+#[cfg(feature = "tls12")]
+#[test]
+fn left_out_of_provider_with_tls12() {
+    use common::TLS12;
+
+    assert!(provider()
+        .kx_groups
+        .iter()
+        .all(|group| group.name() != X25519MLKEM768));
+    assert!(tls13_provider()
+        .cipher_suites
+        .iter()
+        .all(|suite| suite.tls13().is_some()));
+
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    for client in [provider(), ring::default_provider()] {
+        let mut peers = Peers::connect(
+            identity.client_config(client, TLS12),
+            identity.server_config(provider(), TLS12),
+        )
+        .unwrap();
+        assert_eq!(group(&peers), NamedGroup::X25519);
+        peers.round_trip(b"hello");
+    }
+}
+
+#[cfg(feature = "tls12")]
+#[test]
+fn builder_rejects_tls12_suites_with_mlkem() {
+    for groups in [&[X25519MLKEM768][..], &[X25519MLKEM768, NamedGroup::X25519]] {
+        let builder = ProviderBuilder::new().with_kx_groups(groups);
+        assert!(builder.clone().build().is_err());
+        assert!(builder
+            .with_cipher_suites(&[
+                CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+                CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            ])
+            .build()
+            .is_err());
+    }
+}
+
+#[test]
+fn builder_selects_mlkem_with_tls13_suites() {
+    let tls13 = ProviderBuilder::new()
+        .with_cipher_suites(&[CipherSuite::TLS13_CHACHA20_POLY1305_SHA256])
+        .with_kx_groups(&[X25519MLKEM768])
+        .build()
+        .unwrap();
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let peers = Peers::connect(
+        identity.client_config(tls13, TLS13),
+        identity.server_config(tls13_provider(), TLS13),
+    )
+    .unwrap();
+    assert_eq!(group(&peers), X25519MLKEM768);
+}
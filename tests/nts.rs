@@ -0,0 +1,348 @@
+#![cfg(all(feature = "std", feature = "nts", feature = "ecdsa-verify"))]
+
+mod common;
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{TcpListener, UdpSocket};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_siv::aead::{Aead, Payload};
+use aes_siv::{Aes128SivAead, KeyInit, Nonce};
+use common::{Identity, TLS13};
+use no_std_embedded_demo::nts::{Error, NtsSession};
+use no_std_embedded_demo::sntp;
+use rustls::crypto::ring;
+use rustls::{RootCertStore, ServerConnection, StreamOwned};
+
+const EXPORTER_LABEL: &[u8] = b"EXPORTER-network-time-security";
+const UNIQUE_ID: u16 = 0x0104;
+const COOKIE: u16 = 0x0204;
+const COOKIE_PLACEHOLDER: u16 = 0x0304;
+const AUTHENTICATOR: u16 = 0x0404;
+
+/// What the stand-in NTP server answers to the next request.
+#[derive(Clone, Copy, Debug)]
+enum Reply {
+    Time,
+    /// The time with a broken authenticator.
+    Tampered,
+    /// A reply to another request first, then the time.
+    OtherOriginFirst,
+    /// An NTS NAK, which isn't authenticated.
+    Nak,
+    /// A `RATE` Kiss-o'-Death, authenticated or not.
+    Rate {
+        authenticated: bool,
+    },
+}
+
+/// The keys of a key exchange, from the client to the server and back.
+type Keys = ([u8; 32], [u8; 32]);
+
+fn record(buf: &mut Vec<u8>, typ: u16, body: &[u8]) {
+    buf.extend_from_slice(&typ.to_be_bytes());
+    buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    buf.extend_from_slice(body);
+}
+
+/// An NTP extension field, padded to a multiple of 4 bytes.
+fn extension(buf: &mut Vec<u8>, typ: u16, body: &[u8]) {
+    let len = (4 + body.len()).next_multiple_of(4);
+    buf.extend_from_slice(&typ.to_be_bytes());
+    buf.extend_from_slice(&(len as u16).to_be_bytes());
+    buf.extend_from_slice(body);
+    buf.resize(buf.len() + len - 4 - body.len(), 0);
+}
+
+/// The extension fields of `packet`, with their offsets.
+fn extensions(packet: &[u8]) -> Vec<(u16, usize, &[u8])> {
+    let mut fields = Vec::new();
+    let mut offset = sntp::PACKET_LEN;
+    while offset < packet.len() {
+        let typ = u16::from_be_bytes([packet[offset], packet[offset + 1]]);
+        let len = usize::from(u16::from_be_bytes([packet[offset + 2], packet[offset + 3]]));
+        fields.push((typ, offset, &packet[offset + 4..offset + len]));
+        offset += len;
+    }
+    fields
+}
+
+/// The body of an authenticator field for `plaintext`, with `aad` as associated data.
+fn authenticator(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let nonce = [9; 16];
+    let ciphertext = Aes128SivAead::new(key.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .unwrap();
+    let mut body = Vec::new();
+    body.extend_from_slice(&16u16.to_be_bytes());
+    body.extend_from_slice(&(ciphertext.len() as u16).to_be_bytes());
+    body.extend_from_slice(&nonce);
+    body.extend_from_slice(&ciphertext);
+    body
+}
+
+fn ntp_now() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let fraction = (u64::from(now.subsec_nanos()) << 32) / 1_000_000_000;
+    ((now.as_secs() + 2_208_988_800) << 32) | fraction
+}
+
+/// Answers two NTS-KE requests with 8 cookies and the NTPv4 server at `ntp_port`,
+/// and sends the keys of each to the NTP server.
+fn ke_server(tcp: TcpListener, identity: &Identity, ntp_port: u16, keys: Sender<Keys>) {
+    let mut config = identity.server_config(ring::default_provider(), TLS13);
+    config.alpn_protocols = vec![b"ntske/1".to_vec()];
+    let config = Arc::new(config);
+
+    for _ in 0..2 {
+        let (sock, _) = tcp.accept().unwrap();
+        let mut tls = StreamOwned::new(ServerConnection::new(config.clone()).unwrap(), sock);
+        let mut request = Vec::new();
+        loop {
+            let mut header = [0; 4];
+            tls.read_exact(&mut header).unwrap();
+            let typ = u16::from_be_bytes([header[0], header[1]]);
+            let mut body = vec![0; usize::from(u16::from_be_bytes([header[2], header[3]]))];
+            tls.read_exact(&mut body).unwrap();
+            request.push((typ, body));
+            if typ & 0x7fff == 0 {
+                break;
+            }
+        }
+        // NTPv4, AEAD_AES_SIV_CMAC_256, end of message
+        assert_eq!(
+            request,
+            [
+                (0x8001, vec![0, 0]),
+                (0x8004, vec![0, 15]),
+                (0x8000, vec![])
+            ]
+        );
+        assert_eq!(tls.conn.alpn_protocol(), Some(&b"ntske/1"[..]));
+
+        let (mut c2s, mut s2c) = ([0; 32], [0; 32]);
+        let context = [0, 0, 0, 15, 0];
+        tls.conn
+            .export_keying_material(&mut c2s, EXPORTER_LABEL, Some(&context))
+            .unwrap();
+        let context = [0, 0, 0, 15, 1];
+        tls.conn
+            .export_keying_material(&mut s2c, EXPORTER_LABEL, Some(&context))
+            .unwrap();
+        keys.send((c2s, s2c)).unwrap();
+
+        let mut response = Vec::new();
+        record(&mut response, 0x8001, &[0, 0]);
+        record(&mut response, 0x8004, &[0, 15]);
+        // a warning and an unknown record, both ignored
+        record(&mut response, 0x0003, &[0, 1]);
+        record(&mut response, 0x4242, b"x");
+        for i in 0..8 {
+            record(&mut response, 0x8005, &[i; 100]);
+        }
+        record(&mut response, 0x8006, b"127.0.0.1");
+        record(&mut response, 0x8007, &ntp_port.to_be_bytes());
+        record(&mut response, 0x8000, &[]);
+        tls.write_all(&response).unwrap();
+        tls.flush().unwrap();
+        let _ = tls.read_to_end(&mut Vec::new());
+    }
+}
+
+/// Answers each request as the next of `replies` says, and sends the first byte of its
+/// cookie and the number of placeholders to `seen`.
+fn ntp_server(
+    udp: UdpSocket,
+    keys: Receiver<Keys>,
+    replies: Receiver<Reply>,
+    seen: Sender<(u8, usize)>,
+) {
+    let mut current = None;
+    let mut used = HashSet::new();
+    let mut next_cookie = 100;
+    loop {
+        let mut buf = [0; 2048];
+        let Ok((len, from)) = udp.recv_from(&mut buf) else {
+            return;
+        };
+        let request = &buf[..len];
+        let Ok(reply) = replies.recv() else {
+            return;
+        };
+        if let Ok(keys) = keys.try_recv() {
+            current = Some(keys);
+            used.clear();
+        }
+        let (c2s, s2c) = current.unwrap();
+
+        assert_eq!(request[0], 0x23);
+        let fields = extensions(request);
+        let field = |typ| fields.iter().find(|field| field.0 == typ).unwrap();
+        let unique_id = field(UNIQUE_ID).2;
+        assert_eq!(unique_id.len(), 32);
+        let cookie = field(COOKIE).2;
+        assert!(used.insert(cookie.to_vec()), "a cookie was sent twice");
+        let placeholders = fields
+            .iter()
+            .filter(|field| field.0 == COOKIE_PLACEHOLDER)
+            .count();
+        let &(_, offset, body) = field(AUTHENTICATOR);
+        assert_eq!(offset + 4 + body.len(), len, "the authenticator comes last");
+        let nonce_len = usize::from(u16::from_be_bytes([body[0], body[1]]));
+        let ciphertext_len = usize::from(u16::from_be_bytes([body[2], body[3]]));
+        let plaintext = Aes128SivAead::new(&c2s.into())
+            .decrypt(
+                Nonce::from_slice(&body[4..4 + nonce_len]),
+                Payload {
+                    msg: &body[4 + nonce_len..4 + nonce_len + ciphertext_len],
+                    aad: &request[..offset],
+                },
+            )
+            .expect("the request is authenticated");
+        assert!(plaintext.is_empty());
+        seen.send((cookie[0], placeholders)).unwrap();
+
+        let mut header = vec![0; sntp::PACKET_LEN];
+        header[..2].copy_from_slice(&[0x24, 2]);
+        header[24..32].copy_from_slice(&request[40..48]);
+        let now = ntp_now();
+        header[32..40].copy_from_slice(&now.to_be_bytes());
+        header[40..48].copy_from_slice(&now.to_be_bytes());
+        if let Reply::Nak | Reply::Rate { .. } = reply {
+            header[1] = 0;
+            let code = if let Reply::Nak = reply {
+                b"NTSN"
+            } else {
+                b"RATE"
+            };
+            header[12..16].copy_from_slice(code);
+        }
+        extension(&mut header, UNIQUE_ID, unique_id);
+
+        let mut cookies = Vec::new();
+        for _ in 0..=placeholders {
+            extension(&mut cookies, COOKIE, &[next_cookie; 100]);
+            next_cookie += 1;
+        }
+        let mut packet = header.clone();
+        match reply {
+            Reply::Nak
+            | Reply::Rate {
+                authenticated: false,
+            } => {}
+            _ => {
+                let mut body = authenticator(&s2c, &header, &cookies);
+                if let Reply::Tampered = reply {
+                    body[24] ^= 1;
+                }
+                extension(&mut packet, AUTHENTICATOR, &body);
+                // not authenticated, so ignored
+                extension(&mut packet, 0x0504, &[1, 2, 3, 4]);
+            }
+        }
+
+        if let Reply::OtherOriginFirst = reply {
+            let mut other = header.clone();
+            other[24] ^= 1;
+            let body = authenticator(&s2c, &other, &cookies);
+            extension(&mut other, AUTHENTICATOR, &body);
+            udp.send_to(&other, from).unwrap();
+        }
+        udp.send_to(&packet, from).unwrap();
+    }
+}
+
+/// Asserts that `sample` is the time of the stand-in server, i.e. of this host.
+fn assert_now(sample: sntp::Sample) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let difference = now.abs_diff(sample.unix);
+    assert!(difference.as_millis() < 200, "{difference:?}");
+}
+
+#[test]
+fn key_exchange_and_queries() {
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let mut roots = RootCertStore::empty();
+    roots.add(identity.cert.clone()).unwrap();
+
+    let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let ntp_port = udp.local_addr().unwrap().port();
+    let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ke_port = tcp.local_addr().unwrap().port();
+    let (keys_tx, keys_rx) = mpsc::channel();
+    let (replies, replies_rx) = mpsc::channel();
+    let (seen_tx, seen) = mpsc::channel();
+    thread::spawn(move || ke_server(tcp, &identity, ntp_port, keys_tx));
+    thread::spawn(move || ntp_server(udp, keys_rx, replies_rx, seen_tx));
+
+    let mut session = NtsSession::key_exchange("localhost", ke_port, roots.clone()).unwrap();
+    assert_eq!(session.ntp_server(), ("127.0.0.1", ntp_port));
+    assert_eq!(session.cookies(), 8);
+
+    replies.send(Reply::Time).unwrap();
+    assert_now(session.query().unwrap());
+    assert_eq!(seen.recv().unwrap(), (7, 0));
+    assert_eq!(session.cookies(), 8);
+
+    // a tampered reply is dropped, and the next request asks for the lost cookie
+    replies.send(Reply::Tampered).unwrap();
+    replies.send(Reply::Time).unwrap();
+    assert_now(session.query().unwrap());
+    assert_eq!(seen.recv().unwrap(), (100, 0));
+    assert_eq!(seen.recv().unwrap(), (6, 1));
+    assert_eq!(session.cookies(), 8);
+
+    // a reply to another request is skipped
+    replies.send(Reply::OtherOriginFirst).unwrap();
+    assert_now(session.query().unwrap());
+    seen.recv().unwrap();
+    assert_eq!(session.cookies(), 8);
+
+    // an unauthenticated Kiss-o'-Death other than NTSN is dropped like a forged time
+    replies
+        .send(Reply::Rate {
+            authenticated: false,
+        })
+        .unwrap();
+    replies.send(Reply::Time).unwrap();
+    assert_now(session.query().unwrap());
+    seen.recv().unwrap();
+    seen.recv().unwrap();
+    assert_eq!(session.cookies(), 8);
+
+    replies
+        .send(Reply::Rate {
+            authenticated: true,
+        })
+        .unwrap();
+    assert!(matches!(
+        session.query(),
+        Err(Error::Sntp(sntp::Error::KissOfDeath(code))) if code == *b"RATE"
+    ));
+    seen.recv().unwrap();
+    assert_eq!(session.cookies(), 7);
+
+    // an NTS NAK stops the queries until the next key exchange
+    replies.send(Reply::Nak).unwrap();
+    assert!(matches!(
+        session.query(),
+        Err(Error::Sntp(sntp::Error::KissOfDeath(code))) if code == *b"NTSN"
+    ));
+    seen.recv().unwrap();
+    assert_eq!(session.cookies(), 0);
+    assert!(matches!(session.query(), Err(Error::NoCookie)));
+
+    let mut session = NtsSession::key_exchange("localhost", ke_port, roots).unwrap();
+    replies.send(Reply::Time).unwrap();
+    assert_now(session.query().unwrap());
+}
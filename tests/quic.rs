@@ -0,0 +1,165 @@
+#![cfg(all(feature = "std", feature = "chacha"))]
+
+mod common;
+
+use common::hex;
+use no_std_embedded_demo::TLS13_CHACHA20_POLY1305_SHA256;
+use rustls::crypto::cipher::{AeadKey, Iv};
+use rustls::crypto::tls13::OkmBlock;
+use rustls::quic::{self, HeaderProtectionKey, PacketKey};
+
+// RFC9001 A.5: a short header packet carrying a PING frame, protected with ChaCha20-Poly1305
+const SECRET: &str = "9ac312a7f877468ebe69422748ad00a15443f18203a07d6060f688f30f21632b";
+const KEY: &str = "c6d98ff3441c3fe1b2182094f69caa2ed4b716b65488960a7a984979fb23e1c8";
+const IV: &str = "e0459b3474bdd0e44a41c144";
+const HP: &str = "25a282b9e82f06f21f488917a4fc8f1b73573685608597d0efcb076b0ab7a7a4";
+const KU: &str = "1223504755036d556342ee9361d253421a826c9ecdf3c7148684b36b714881f9";
+const PACKET_NUMBER: u64 = 654360564;
+const HEADER: &str = "4200bff4";
+const PAYLOAD: &str = "01";
+const SAMPLE: &str = "5e5cd55c41f69080575d7999c25a5bfb";
+const PROTECTED: &str = "4cfe4189655e5cd55c41f69080575d7999c25a5bfb";
+
+fn algorithm() -> &'static dyn quic::Algorithm {
+    TLS13_CHACHA20_POLY1305_SHA256
+        .tls13()
+        .unwrap()
+        .quic
+        .unwrap()
+}
+
+/// HKDF-Expand-Label of RFC8446 section 7.1, with an empty context.
+fn expand_label(label: &[u8], len: usize) -> Vec<u8> {
+    let suite = TLS13_CHACHA20_POLY1305_SHA256.tls13().unwrap();
+    let expander = suite
+        .hkdf_provider
+        .expander_for_okm(&OkmBlock::new(&hex(SECRET)));
+    let mut output = vec![0; len];
+    expander
+        .expand_slice(
+            &[
+                &(len as u16).to_be_bytes(),
+                &[(b"tls13 ".len() + label.len()) as u8],
+                b"tls13 ",
+                label,
+                &[0],
+            ],
+            &mut output,
+        )
+        .unwrap();
+    output
+}
+
+fn keys() -> (Box<dyn PacketKey>, Box<dyn HeaderProtectionKey>) {
+    let key: [u8; 32] = hex(KEY).try_into().unwrap();
+    let iv: [u8; 12] = hex(IV).try_into().unwrap();
+    let hp: [u8; 32] = hex(HP).try_into().unwrap();
+    (
+        algorithm().packet_key(AeadKey::from(key), Iv::from(iv)),
+        algorithm().header_protection_key(AeadKey::from(hp)),
+    )
+}
+
+#[test]
+fn keys_from_secret() {
+    assert_eq!(expand_label(b"quic key", 32), hex(KEY));
+    assert_eq!(expand_label(b"quic iv", 12), hex(IV));
+    assert_eq!(expand_label(b"quic hp", 32), hex(HP));
+    assert_eq!(expand_label(b"quic ku", 32), hex(KU));
+}
+
+#[test]
+fn packet_protection() {
+    let (packet_key, _) = keys();
+    let header = hex(HEADER);
+    let mut payload = hex(PAYLOAD);
+    let tag = packet_key
+        .encrypt_in_place(PACKET_NUMBER, &header, &mut payload)
+        .unwrap();
+    payload.extend_from_slice(tag.as_ref());
+    assert_eq!(payload, hex(PROTECTED)[header.len()..]);
+
+    let plaintext = packet_key
+        .decrypt_in_place(PACKET_NUMBER, &header, &mut payload.clone())
+        .unwrap()
+        .to_vec();
+    assert_eq!(plaintext, hex(PAYLOAD));
+    assert!(packet_key
+        .decrypt_in_place(PACKET_NUMBER + 1, &header, &mut payload.clone())
+        .is_err());
+    let mut tampered = payload.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(packet_key
+        .decrypt_in_place(PACKET_NUMBER, &header, &mut tampered)
+        .is_err());
+    assert!(packet_key
+        .decrypt_in_place(PACKET_NUMBER, &hex("4200bff5"), &mut payload)
+        .is_err());
+}
+
+#[test]
+fn header_protection() {
+    let (_, header_key) = keys();
+    // four bytes after the start of the 3-byte packet number
+    assert_eq!(hex(PROTECTED)[5..21], hex(SAMPLE));
+    let mut header = hex(HEADER);
+    let (first, packet_number) = header.split_at_mut(1);
+    header_key
+        .encrypt_in_place(&hex(SAMPLE), &mut first[0], packet_number)
+        .unwrap();
+    assert_eq!(header, hex(PROTECTED)[..4]);
+
+    let (first, packet_number) = header.split_at_mut(1);
+    header_key
+        .decrypt_in_place(&hex(SAMPLE), &mut first[0], packet_number)
+        .unwrap();
+    assert_eq!(header, hex(HEADER));
+
+    assert_eq!(header_key.sample_len(), 16);
+    let (first, packet_number) = header.split_at_mut(1);
+    assert!(header_key
+        .encrypt_in_place(&hex(SAMPLE)[..15], &mut first[0], packet_number)
+        .is_err());
+}
+
+/// The sample comes from the peer, so its first 4 bytes may be the last ChaCha20 block counter.
+#[test]
+fn header_protection_with_the_last_block_counter() {
+    let (_, header_key) = keys();
+    let ring = rustls::crypto::ring::cipher_suite::TLS13_CHACHA20_POLY1305_SHA256
+        .tls13()
+        .unwrap()
+        .quic
+        .unwrap()
+        .header_protection_key(AeadKey::from(<[u8; 32]>::try_from(hex(HP)).unwrap()));
+    for sample in [
+        hex("ffffffff000000000000000000000000"),
+        hex("ffffffff5e5cd55c41f69080575d7999"),
+        hex("feffffff000000000000000000000000"),
+    ] {
+        let mut header = hex("c301020304");
+        let mut expected = header.clone();
+        let (first, packet_number) = header.split_at_mut(1);
+        header_key
+            .encrypt_in_place(&sample, &mut first[0], packet_number)
+            .unwrap();
+        let (first, packet_number) = expected.split_at_mut(1);
+        ring.encrypt_in_place(&sample, &mut first[0], packet_number)
+            .unwrap();
+        assert_eq!(header, expected, "{sample:02x?}");
+
+        let (first, packet_number) = header.split_at_mut(1);
+        header_key
+            .decrypt_in_place(&sample, &mut first[0], packet_number)
+            .unwrap();
+        assert_eq!(header, hex("c301020304"), "{sample:02x?}");
+    }
+}
+
+#[test]
+fn limits() {
+    let (packet_key, _) = keys();
+    assert_eq!(packet_key.tag_len(), 16);
+    assert_eq!(packet_key.integrity_limit(), 1 << 36);
+    assert_eq!(packet_key.confidentiality_limit(), u64::MAX);
+}
@@ -0,0 +1,84 @@
+#![cfg(all(feature = "std", feature = "ecdsa-sign", feature = "ecdsa-verify"))]
+
+mod common;
+
+use std::io::Write;
+
+use common::{Identity, Peers};
+use no_std_embedded_demo::provider;
+use rustls::crypto::CryptoProvider;
+use rustls::SupportedCipherSuite;
+
+/// Connects with only `suite`, then lets the client send a record that `tamper` changes.
+fn tampered_record(
+    suite: SupportedCipherSuite,
+    identity: &Identity,
+    tamper: fn(&mut Vec<u8>),
+) -> rustls::Error {
+    let provider = CryptoProvider {
+        cipher_suites: vec![suite],
+        ..provider()
+    };
+    let versions = &[suite.version()];
+    let mut peers = Peers::connect(
+        identity.client_config(provider.clone(), versions),
+        identity.server_config(provider, versions),
+    )
+    .unwrap();
+    peers.round_trip(b"hello");
+
+    peers.client.writer().write_all(b"hello").unwrap();
+    let mut record = Vec::new();
+    peers.client.write_tls(&mut record).unwrap();
+    tamper(&mut record);
+    peers.server.read_tls(&mut &record[..]).unwrap();
+    peers.server.process_new_packets().unwrap_err()
+}
+
+/// Keeps `len` bytes of the record's payload.
+fn truncate(record: &mut Vec<u8>, len: usize) {
+    record.truncate(5 + len);
+    record[3..5].copy_from_slice(&(len as u16).to_be_bytes());
+}
+
+/// Every suite, so the AES-128 and CCM ciphers and the TLS1.2 record layer are covered too.
+#[test]
+fn tampered_and_truncated_records_are_rejected() {
+    let ecdsa = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    #[cfg(all(feature = "rsa-sign", feature = "rsa-verify"))]
+    let rsa = Identity::rsa();
+    for suite in provider().cipher_suites {
+        let identity = match suite {
+            #[cfg(feature = "tls12")]
+            SupportedCipherSuite::Tls12(tls12)
+                if !tls12
+                    .sign
+                    .contains(&rustls::SignatureScheme::ECDSA_NISTP256_SHA256) =>
+            {
+                #[cfg(all(feature = "rsa-sign", feature = "rsa-verify"))]
+                {
+                    &rsa
+                }
+                #[cfg(not(all(feature = "rsa-sign", feature = "rsa-verify")))]
+                continue;
+            }
+            _ => &ecdsa,
+        };
+
+        let tampers: [fn(&mut Vec<u8>); 4] = [
+            |record| *record.last_mut().unwrap() ^= 1,
+            |record| record[5] ^= 1,
+            // one byte short, and shorter than any tag
+            |record| truncate(record, record.len() - 6),
+            |record| truncate(record, 4),
+        ];
+        for (i, tamper) in tampers.into_iter().enumerate() {
+            assert_eq!(
+                tampered_record(suite, identity, tamper),
+                rustls::Error::DecryptError,
+                "{:?}, tamper #{i}",
+                suite.suite()
+            );
+        }
+    }
+}
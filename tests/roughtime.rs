@@ -0,0 +1,290 @@
+#![cfg(all(feature = "std", feature = "roughtime"))]
+
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ed25519_dalek::{Signer, SigningKey};
+use no_std_embedded_demo::roughtime::{combine, Error, Request, Sample, REQUEST_LEN};
+use sha2::{Digest, Sha512};
+
+const PUBLIC_KEY: &[u8; 32] = include_bytes!("data/roughtime/public_key");
+const REPLY: &[u8] = include_bytes!("data/roughtime/reply");
+const NONCE: [u8; 64] = [12; 64];
+/// The midpoint of the generated replies, in microseconds.
+const MIDPOINT: u64 = 1_760_000_000_000_000;
+const DAY: u64 = 86_400_000_000;
+
+/// A Roughtime message: the tags in increasing order, with their values.
+fn message(mut fields: Vec<(&[u8; 4], Vec<u8>)>) -> Vec<u8> {
+    fields.sort_by_key(|(tag, _)| u32::from_le_bytes(**tag));
+    let mut message = (fields.len() as u32).to_le_bytes().to_vec();
+    let mut offset = 0;
+    for (_, value) in &fields[..fields.len() - 1] {
+        offset += value.len() as u32;
+        message.extend(offset.to_le_bytes());
+    }
+    for (tag, _) in &fields {
+        message.extend(**tag);
+    }
+    for (_, value) in &fields {
+        message.extend(value);
+    }
+    message
+}
+
+fn leaf(nonce: &[u8]) -> [u8; 64] {
+    Sha512::new()
+        .chain_update([0])
+        .chain_update(nonce)
+        .finalize()
+        .into()
+}
+
+fn node(left: &[u8], right: &[u8]) -> [u8; 64] {
+    Sha512::new()
+        .chain_update([1])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// The reply to [`NONCE`], at index 2 of a batch of the nonces `[10; 64]` to `[13; 64]`,
+/// with a midpoint of [`MIDPOINT`] and a radius of 1 s, and the online key valid from
+/// `min_time` to `max_time`.
+fn generate_reply(min_time: u64, max_time: u64) -> Vec<u8> {
+    let long_term = SigningKey::from_bytes(&[1; 32]);
+    let online = SigningKey::from_bytes(&[2; 32]);
+
+    let leaves = [[10; 64], [11; 64], NONCE, [13; 64]].map(|nonce| leaf(&nonce));
+    let (left, right) = (node(&leaves[0], &leaves[1]), node(&leaves[2], &leaves[3]));
+    let signed_response = message(vec![
+        (b"ROOT", node(&left, &right).to_vec()),
+        (b"MIDP", MIDPOINT.to_le_bytes().to_vec()),
+        (b"RADI", 1_000_000u32.to_le_bytes().to_vec()),
+    ]);
+    let delegation = message(vec![
+        (b"MINT", min_time.to_le_bytes().to_vec()),
+        (b"MAXT", max_time.to_le_bytes().to_vec()),
+        (b"PUBK", online.verifying_key().to_bytes().to_vec()),
+    ]);
+    let delegation_signature =
+        long_term.sign(&[&b"RoughTime v1 delegation signature--\x00"[..], &delegation].concat());
+    let certificate = message(vec![
+        (b"DELE", delegation),
+        (b"SIG\x00", delegation_signature.to_vec()),
+    ]);
+    let signature = online.sign(
+        &[
+            &b"RoughTime v1 response signature\x00"[..],
+            &signed_response,
+        ]
+        .concat(),
+    );
+    message(vec![
+        (b"SIG\x00", signature.to_vec()),
+        (b"PATH", [leaves[3], left].concat()),
+        (b"SREP", signed_response),
+        (b"CERT", certificate),
+        (b"INDX", 2u32.to_le_bytes().to_vec()),
+    ])
+}
+
+/// The files of `data/roughtime`, see its README.
+fn fixtures() -> [(&'static str, Vec<u8>); 4] {
+    let public_key = SigningKey::from_bytes(&[1; 32]).verifying_key();
+    [
+        ("public_key", public_key.to_bytes().to_vec()),
+        ("reply", generate_reply(MIDPOINT - DAY, MIDPOINT + DAY)),
+        (
+            "reply_expired_delegation",
+            generate_reply(MIDPOINT - 2 * DAY, MIDPOINT - DAY),
+        ),
+        (
+            "reply_future_delegation",
+            generate_reply(MIDPOINT + DAY, MIDPOINT + 2 * DAY),
+        ),
+    ]
+}
+
+fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/roughtime")
+}
+
+#[test]
+fn fixtures_match_their_generator() {
+    // Ed25519 signatures are deterministic
+    for (name, generated) in fixtures() {
+        let committed = std::fs::read(data_dir().join(name)).unwrap();
+        assert!(committed == generated, "{name} differs, run write_fixtures");
+    }
+}
+
+/// Rewrites the files of `data/roughtime` after a change to [`generate_reply`].
+#[test]
+#[ignore]
+fn write_fixtures() {
+    for (name, generated) in fixtures() {
+        std::fs::write(data_dir().join(name), generated).unwrap();
+    }
+}
+
+/// Records the reply of the server at `ROUGHTIME_SERVER` (`host:port`) to [`NONCE`] in
+/// `data/roughtime/captured/reply`, after checking it with the long-term key in
+/// `ROUGHTIME_PUBLIC_KEY` (hex), which goes to `captured/public_key`.
+#[test]
+#[ignore]
+fn capture_reply() {
+    let server = std::env::var("ROUGHTIME_SERVER").unwrap();
+    let hex = std::env::var("ROUGHTIME_PUBLIC_KEY").unwrap();
+    let public_key: [u8; 32] = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+
+    let sock = UdpSocket::bind("[::]:0").unwrap();
+    sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let request = Request::new(NONCE, 0);
+    sock.send_to(&request.packet(), &server).unwrap();
+    let mut reply = [0; REQUEST_LEN];
+    let len = sock.recv(&mut reply).unwrap();
+    let sample = request.reply(&reply[..len], &public_key, 0).unwrap();
+    println!("{sample:?}");
+
+    let captured = data_dir().join("captured");
+    std::fs::create_dir_all(&captured).unwrap();
+    std::fs::write(captured.join("reply"), &reply[..len]).unwrap();
+    std::fs::write(captured.join("public_key"), public_key).unwrap();
+}
+
+#[test]
+fn request() {
+    let packet = Request::new(NONCE, 0).packet();
+    assert_eq!(packet.len(), REQUEST_LEN);
+    assert_eq!(packet[..8], [2, 0, 0, 0, 64, 0, 0, 0]);
+    assert_eq!(packet[8..16], *b"NONCPAD\xff");
+    assert_eq!(packet[16..80], NONCE);
+    assert!(packet[80..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn valid_reply() {
+    // received 40ms after the request was sent
+    let sample = Request::new(NONCE, 1_000)
+        .reply(REPLY, PUBLIC_KEY, 41_000)
+        .unwrap();
+    assert_eq!(
+        sample,
+        Sample {
+            unix: Duration::from_micros(MIDPOINT + 20_000),
+            radius: Duration::from_micros(1_020_000),
+            at: 41_000,
+        }
+    );
+}
+
+#[test]
+fn nonce_mismatch() {
+    // another request of the same batch, and one that isn't in it
+    for nonce in [[11; 64], [9; 64]] {
+        assert_eq!(
+            Request::new(nonce, 0).reply(REPLY, PUBLIC_KEY, 0),
+            Err(Error::NonceMismatch)
+        );
+    }
+}
+
+#[test]
+fn tampered_signed_response() {
+    let request = Request::new(NONCE, 0);
+    // the midpoint and the radius are signed, but not part of the Merkle tree
+    let midpoint = REPLY
+        .windows(8)
+        .position(|w| w == MIDPOINT.to_le_bytes())
+        .unwrap();
+    let radius = REPLY
+        .windows(4)
+        .position(|w| w == 1_000_000u32.to_le_bytes())
+        .unwrap();
+    for at in [midpoint, midpoint + 7, radius] {
+        let mut reply = REPLY.to_vec();
+        reply[at] ^= 1;
+        assert_eq!(
+            request.reply(&reply, PUBLIC_KEY, 0),
+            Err(Error::BadSignature)
+        );
+    }
+}
+
+#[test]
+fn wrong_long_term_key() {
+    let other = SigningKey::from_bytes(&[3; 32]).verifying_key().to_bytes();
+    assert_eq!(
+        Request::new(NONCE, 0).reply(REPLY, &other, 0),
+        Err(Error::BadDelegation)
+    );
+}
+
+#[test]
+fn midpoint_outside_delegation() {
+    let request = Request::new(NONCE, 0);
+    for reply in [
+        &include_bytes!("data/roughtime/reply_expired_delegation")[..],
+        &include_bytes!("data/roughtime/reply_future_delegation")[..],
+    ] {
+        assert_eq!(
+            request.reply(reply, PUBLIC_KEY, 0),
+            Err(Error::OutsideDelegation)
+        );
+    }
+}
+
+#[test]
+fn malformed_replies() {
+    let request = Request::new(NONCE, 0);
+    assert_eq!(request.reply(&[], PUBLIC_KEY, 0), Err(Error::Malformed));
+    assert_eq!(
+        request.reply(&[0xff; 64], PUBLIC_KEY, 0),
+        Err(Error::Malformed)
+    );
+    for len in 0..REPLY.len() {
+        assert!(request.reply(&REPLY[..len], PUBLIC_KEY, 0).is_err());
+    }
+}
+
+fn sample(unix: u64, radius: u64, at: u64) -> Sample {
+    Sample {
+        unix: Duration::from_secs(unix),
+        radius: Duration::from_secs(radius),
+        at,
+    }
+}
+
+#[test]
+fn combine_overlapping_samples() {
+    assert_eq!(combine(&[]), None);
+    assert_eq!(combine(&[sample(100, 5, 0)]), Some(sample(100, 5, 0)));
+    // [95, 105] and [101, 107]
+    assert_eq!(
+        combine(&[sample(100, 5, 0), sample(104, 3, 0)]),
+        Some(sample(103, 2, 0))
+    );
+    // the second one 10s later on the monotonic clock: [105, 115] and [107, 117]
+    assert_eq!(
+        combine(&[sample(100, 5, 0), sample(112, 5, 10_000_000)]),
+        Some(sample(111, 4, 10_000_000))
+    );
+}
+
+#[test]
+fn combine_disjoint_samples() {
+    assert_eq!(combine(&[sample(100, 1, 0), sample(104, 1, 0)]), None);
+    // each overlaps the first one, but not each other
+    assert_eq!(
+        combine(&[sample(100, 10, 0), sample(95, 2, 0), sample(105, 2, 0)]),
+        None
+    );
+}
@@ -0,0 +1,314 @@
+#![cfg(feature = "std")]
+
+use no_std_embedded_demo::provider;
+use rustls::pki_types::{
+    AlgorithmIdentifier, PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer,
+    SignatureVerificationAlgorithm,
+};
+#[cfg(any(
+    all(feature = "ecdsa-sign", feature = "ecdsa-verify"),
+    all(feature = "ed25519-sign", feature = "ed25519-verify"),
+    all(feature = "rsa-sign", feature = "rsa-verify")
+))]
+use rustls::SignatureScheme;
+use webpki::alg_id;
+use wycheproof::TestResult;
+
+/// Our verifier for a pair of algorithm identifiers.
+fn verifier(
+    public_key: AlgorithmIdentifier,
+    signature: AlgorithmIdentifier,
+) -> &'static dyn SignatureVerificationAlgorithm {
+    *provider()
+        .signature_verification_algorithms
+        .all
+        .iter()
+        .find(|alg| alg.public_key_alg_id() == public_key && alg.signature_alg_id() == signature)
+        .unwrap()
+}
+
+/// Checks a Wycheproof signature vector; acceptable ones may go either way.
+fn check(
+    verifier: &dyn SignatureVerificationAlgorithm,
+    public_key: &[u8],
+    test: (&[u8], &[u8], TestResult, usize),
+) {
+    let (msg, sig, result, tc_id) = test;
+    let verified = verifier.verify_signature(public_key, msg, sig).is_ok();
+    match result {
+        TestResult::Valid => assert!(verified, "{verifier:?} test {tc_id}"),
+        TestResult::Invalid => assert!(!verified, "{verifier:?} test {tc_id}"),
+        TestResult::Acceptable => {}
+    }
+}
+
+#[cfg(feature = "ecdsa-verify")]
+#[test]
+fn ecdsa_wycheproof() {
+    use wycheproof::ecdsa::{TestName, TestSet};
+
+    for (name, public_key, signature) in [
+        (
+            TestName::EcdsaSecp256r1Sha256,
+            alg_id::ECDSA_P256,
+            alg_id::ECDSA_SHA256,
+        ),
+        (
+            TestName::EcdsaSecp384r1Sha256,
+            alg_id::ECDSA_P384,
+            alg_id::ECDSA_SHA256,
+        ),
+        (
+            TestName::EcdsaSecp384r1Sha384,
+            alg_id::ECDSA_P384,
+            alg_id::ECDSA_SHA384,
+        ),
+    ] {
+        let verifier = verifier(public_key, signature);
+        for group in TestSet::load(name).unwrap().test_groups {
+            for test in &group.tests {
+                let test = (&test.msg[..], &test.sig[..], test.result, test.tc_id);
+                check(verifier, &group.key.key, test);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ed25519-verify")]
+#[test]
+fn ed25519_wycheproof() {
+    use wycheproof::eddsa::{TestName, TestSet};
+
+    let verifier = verifier(alg_id::ED25519, alg_id::ED25519);
+    for group in TestSet::load(TestName::Ed25519).unwrap().test_groups {
+        for test in &group.tests {
+            let test = (&test.msg[..], &test.sig[..], test.result, test.tc_id);
+            check(verifier, &group.key.pk, test);
+        }
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
+#[test]
+fn rsa_pkcs1_wycheproof() {
+    use wycheproof::rsa_pkcs1_verify::{TestName, TestSet};
+
+    for (name, signature) in [
+        (TestName::Rsa2048Sha256, alg_id::RSA_PKCS1_SHA256),
+        (TestName::Rsa2048Sha384, alg_id::RSA_PKCS1_SHA384),
+        (TestName::Rsa2048Sha512, alg_id::RSA_PKCS1_SHA512),
+        (TestName::Rsa3072Sha256, alg_id::RSA_PKCS1_SHA256),
+        (TestName::Rsa4096Sha512, alg_id::RSA_PKCS1_SHA512),
+    ] {
+        let verifier = verifier(alg_id::RSA_ENCRYPTION, signature);
+        for group in TestSet::load(name).unwrap().test_groups {
+            for test in &group.tests {
+                let test = (&test.msg[..], &test.sig[..], test.result, test.tc_id);
+                check(verifier, &group.asn_key, test);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rsa-verify")]
+#[test]
+fn rsa_pss_wycheproof() {
+    use wycheproof::rsa_pss_verify::{TestName, TestSet};
+
+    // TLS only uses a salt as long as the hash
+    for (name, signature) in [
+        (
+            TestName::RsaPss2048Sha256Mgf1SaltLen32,
+            alg_id::RSA_PSS_SHA256,
+        ),
+        (
+            TestName::RsaPss3072Sha256Mgf1SaltLen32,
+            alg_id::RSA_PSS_SHA256,
+        ),
+        (
+            TestName::RsaPss2048Sha384Mgf1SaltLen48,
+            alg_id::RSA_PSS_SHA384,
+        ),
+        (
+            TestName::RsaPss4096Sha512Mgf1SaltLen64,
+            alg_id::RSA_PSS_SHA512,
+        ),
+    ] {
+        let verifier = verifier(alg_id::RSA_ENCRYPTION, signature);
+        for group in TestSet::load(name).unwrap().test_groups {
+            for test in &group.tests {
+                let test = (&test.msg[..], &test.sig[..], test.result, test.tc_id);
+                check(verifier, &group.asn_key, test);
+            }
+        }
+    }
+}
+
+#[test]
+fn verifiers_reject_malformed_public_keys() {
+    for verifier in provider().signature_verification_algorithms.all {
+        for public_key in [
+            &[][..],
+            &[0x04; 65],
+            &[0x30, 0x03, 0x02, 0x01],
+            &[0xff; 32][..31],
+        ] {
+            assert!(
+                verifier
+                    .verify_signature(public_key, b"message", &[0x30, 0x00])
+                    .is_err(),
+                "{verifier:?} {public_key:02x?}"
+            );
+        }
+    }
+}
+
+/// Signs with our key and verifies with *ring*'s algorithms for the scheme, then with ours.
+#[cfg(any(
+    all(feature = "ecdsa-sign", feature = "ecdsa-verify"),
+    all(feature = "ed25519-sign", feature = "ed25519-verify"),
+    all(feature = "rsa-sign", feature = "rsa-verify")
+))]
+fn sign_and_verify(key: PrivateKeyDer<'static>, public_key: &[u8], scheme: SignatureScheme) {
+    let key = provider().key_provider.load_private_key(key).unwrap();
+    let signer = key.choose_scheme(&[scheme]).unwrap();
+    assert_eq!(signer.scheme(), scheme);
+    let signature = signer.sign(b"message").unwrap();
+
+    for provider in [rustls::crypto::ring::default_provider(), provider()] {
+        let mapping = provider.signature_verification_algorithms.mapping;
+        let (_, algs) = mapping.iter().find(|(s, _)| *s == scheme).unwrap();
+        assert!(
+            algs.iter().any(|alg| alg
+                .verify_signature(public_key, b"message", &signature)
+                .is_ok()),
+            "{scheme:?}"
+        );
+        assert!(
+            !algs.iter().any(|alg| alg
+                .verify_signature(public_key, b"massage", &signature)
+                .is_ok()),
+            "{scheme:?}"
+        );
+    }
+}
+
+#[cfg(all(feature = "ecdsa-sign", feature = "ecdsa-verify"))]
+#[test]
+fn ecdsa_signers() {
+    for (alg, scheme) in [
+        (
+            &rcgen::PKCS_ECDSA_P256_SHA256,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+        ),
+        (
+            &rcgen::PKCS_ECDSA_P384_SHA384,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+        ),
+    ] {
+        let key = rcgen::KeyPair::generate_for(alg).unwrap();
+        let der = PrivatePkcs8KeyDer::from(key.serialize_der());
+        sign_and_verify(der.into(), key.public_key_raw(), scheme);
+        // a P-256 key doesn't sign with P-384's scheme, and the other way round
+        let key = provider()
+            .key_provider
+            .load_private_key(PrivatePkcs8KeyDer::from(key.serialize_der()).into())
+            .unwrap();
+        assert!(key
+            .choose_scheme(&[
+                SignatureScheme::ECDSA_NISTP256_SHA256,
+                SignatureScheme::ECDSA_NISTP384_SHA384
+            ])
+            .is_some_and(|signer| signer.scheme() == scheme));
+    }
+}
+
+#[cfg(all(feature = "ed25519-sign", feature = "ed25519-verify"))]
+#[test]
+fn ed25519_signer() {
+    let key = rcgen::KeyPair::generate_for(&rcgen::PKCS_ED25519).unwrap();
+    let der = PrivatePkcs8KeyDer::from(key.serialize_der());
+    sign_and_verify(der.into(), key.public_key_raw(), SignatureScheme::ED25519);
+}
+
+#[cfg(all(feature = "rsa-sign", feature = "rsa-verify"))]
+#[test]
+fn rsa_signer() {
+    use rsa::pkcs1::{EncodeRsaPrivateKey, EncodeRsaPublicKey};
+    use rsa::pkcs8::EncodePrivateKey;
+
+    let key = rsa::RsaPrivateKey::new(&mut rand_core::OsRng, 2048).unwrap();
+    let public_key = key.to_public_key().to_pkcs1_der().unwrap();
+    for scheme in [
+        SignatureScheme::RSA_PSS_SHA384,
+        SignatureScheme::RSA_PSS_SHA256,
+        SignatureScheme::RSA_PKCS1_SHA384,
+        SignatureScheme::RSA_PKCS1_SHA256,
+    ] {
+        let pkcs8 = PrivatePkcs8KeyDer::from(key.to_pkcs8_der().unwrap().as_bytes().to_vec());
+        sign_and_verify(pkcs8.into(), public_key.as_bytes(), scheme);
+        let pkcs1 = PrivatePkcs1KeyDer::from(key.to_pkcs1_der().unwrap().as_bytes().to_vec());
+        sign_and_verify(pkcs1.into(), public_key.as_bytes(), scheme);
+    }
+}
+
+#[test]
+fn malformed_private_keys() {
+    let mut keys: Vec<PrivateKeyDer<'static>> = vec![
+        PrivatePkcs8KeyDer::from(b"not a key".to_vec()).into(),
+        PrivatePkcs1KeyDer::from(b"not a key".to_vec()).into(),
+        PrivateSec1KeyDer::from(b"not a key".to_vec()).into(),
+        PrivatePkcs8KeyDer::from(vec![0x30, 0x00]).into(),
+        // an X25519 key (RFC8410 section 10.3), which can't sign
+        PrivatePkcs8KeyDer::from(
+            [
+                &[
+                    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x04,
+                    0x22, 0x04, 0x20,
+                ][..],
+                &[0x42; 32],
+            ]
+            .concat(),
+        )
+        .into(),
+    ];
+
+    for alg in [
+        &rcgen::PKCS_ECDSA_P256_SHA256,
+        &rcgen::PKCS_ECDSA_P384_SHA384,
+        &rcgen::PKCS_ED25519,
+    ] {
+        let der = rcgen::KeyPair::generate_for(alg).unwrap().serialize_der();
+        // cut short
+        for len in [der.len() / 2, der.len() - 1] {
+            keys.push(PrivatePkcs8KeyDer::from(der[..len].to_vec()).into());
+        }
+        // a private scalar of zero
+        if let Some(at) = der
+            .windows(5)
+            .position(|w| w == [0x02, 0x01, 0x01, 0x04, 0x20])
+        {
+            let mut zero = der.clone();
+            zero[at + 5..at + 37].fill(0);
+            keys.push(PrivatePkcs8KeyDer::from(zero).into());
+        }
+        if let Some(at) = der
+            .windows(5)
+            .position(|w| w == [0x02, 0x01, 0x01, 0x04, 0x30])
+        {
+            let mut zero = der.clone();
+            zero[at + 5..at + 53].fill(0);
+            keys.push(PrivatePkcs8KeyDer::from(zero).into());
+        }
+    }
+
+    for key in keys {
+        assert!(
+            provider()
+                .key_provider
+                .load_private_key(key.clone_key())
+                .is_err(),
+            "{key:?}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(all(feature = "std", feature = "tls12", feature = "ecdsa-verify"))]
+
+mod common;
+
+use common::{Identity, Peers, TLS12};
+use no_std_embedded_demo::ProviderBuilder;
+use rustls::crypto::ring;
+use rustls::{CipherSuite, ProtocolVersion};
+
+/// Connects with only `suite` to a stock rustls server with an ECDSA certificate that only
+/// speaks TLS1.2.
+fn connect_to_ecdsa_server(suite: CipherSuite) {
+    let identity = Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256);
+    let client = ProviderBuilder::new()
+        .with_cipher_suites(&[suite])
+        .build()
+        .unwrap();
+    let mut peers = Peers::connect(
+        identity.client_config(client, TLS12),
+        identity.server_config(ring::default_provider(), TLS12),
+    )
+    .unwrap();
+    assert_eq!(
+        peers.client.protocol_version(),
+        Some(ProtocolVersion::TLSv1_2)
+    );
+    assert_eq!(
+        peers.client.negotiated_cipher_suite().unwrap().suite(),
+        suite
+    );
+    peers.round_trip(b"GET / HTTP/1.1\r\n\r\n");
+}
+
+#[cfg(feature = "chacha")]
+#[test]
+fn ecdsa_chacha20_poly1305() {
+    connect_to_ecdsa_server(CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256);
+}
+
+#[cfg(feature = "aes-gcm")]
+#[test]
+fn ecdsa_aes_128_gcm() {
+    connect_to_ecdsa_server(CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256);
+}
+
+#[cfg(feature = "aes-gcm")]
+#[test]
+fn ecdsa_aes_256_gcm() {
+    connect_to_ecdsa_server(CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384);
+}
+
+/// Application data in both directions, over several records, with our provider on either
+/// side of a stock rustls peer.
+#[cfg(feature = "chacha")]
+fn exchange_data(suite: CipherSuite, identity: Identity) {
+    let ours = || {
+        ProviderBuilder::new()
+            .with_cipher_suites(&[suite])
+            .build()
+            .unwrap()
+    };
+    let data: Vec<u8> = (0..40_000u32).map(|i| i as u8).collect();
+    for (client, server) in [
+        (ours(), ring::default_provider()),
+        (ring::default_provider(), ours()),
+    ] {
+        let mut peers = Peers::connect(
+            identity.client_config(client, TLS12),
+            identity.server_config(server, TLS12),
+        )
+        .unwrap();
+        assert_eq!(
+            peers.server.negotiated_cipher_suite().unwrap().suite(),
+            suite
+        );
+        peers.round_trip(&data);
+        peers.round_trip(b"x");
+    }
+}
+
+#[cfg(all(feature = "chacha", feature = "ecdsa-sign"))]
+#[test]
+fn ecdsa_chacha20_poly1305_data() {
+    exchange_data(
+        CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+        Identity::generate(&rcgen::PKCS_ECDSA_P256_SHA256),
+    );
+}
+
+#[cfg(all(feature = "chacha", feature = "rsa-sign", feature = "rsa-verify"))]
+#[test]
+fn rsa_chacha20_poly1305_data() {
+    exchange_data(
+        CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        Identity::rsa(),
+    );
+}